@@ -7,6 +7,7 @@ fn main() {
     let args: Vec<String> = env::args().collect();
     if args.len() != 3 {
         println!("usage: aoc-2023 [day] [input.txt]");
+        println!("set AOC_EXPORT to a directory to get graphs and traces for some days");
         exit(1);
     }
     let day: usize = str::parse(&args[1]).expect("couldn't parse day");
@@ -36,6 +37,11 @@ fn main() {
         18 => solutions::day18::main(trimmed),
         19 => solutions::day19::main(trimmed),
         20 => solutions::day20::main(trimmed),
+        21 => solutions::day21::main(trimmed),
+        22 => solutions::day22::main(trimmed),
+        23 => solutions::day23::main(trimmed),
+        24 => solutions::day24::main(trimmed),
+        25 => solutions::day25::main(trimmed),
         _ => unimplemented!(),
     }
 }
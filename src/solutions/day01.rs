@@ -42,3 +42,41 @@ pub fn main(input: &str) {
     println!("*  {total_simple}");
     println!("** {total_spelled}");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIMPLE_EXAMPLE: &str = "\
+1abc2
+pqr3stu8vwx
+a1b2c3d4e5f
+treb7uchet";
+
+    const SPELLED_EXAMPLE: &str = "\
+two1nine
+eightwothree
+abcone2threexyz
+xtwone3four
+4nineeightseven2
+zoneight234
+7pqrstsixteen";
+
+    #[test]
+    fn part_one() {
+        let total: u32 = SIMPLE_EXAMPLE
+            .split('\n')
+            .map(|line| simple_calibration(line).unwrap())
+            .sum();
+        assert_eq!(total, 142);
+    }
+
+    #[test]
+    fn part_two() {
+        let total: u32 = SPELLED_EXAMPLE
+            .split('\n')
+            .map(|line| spelled_calibration(line).unwrap())
+            .sum();
+        assert_eq!(total, 281);
+    }
+}
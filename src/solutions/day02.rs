@@ -89,3 +89,40 @@ pub fn main(input: &str) {
     println!("*  {id_sum}");
     println!("** {power_sum}");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
+
+    #[test]
+    fn part_one() {
+        let elf_bag = Bag {
+            red: 12,
+            green: 13,
+            blue: 14,
+        };
+        let id_sum: u32 = EXAMPLE
+            .split('\n')
+            .map(Game::parse)
+            .filter(|game| game.is_possible(&elf_bag))
+            .map(|game| game.id)
+            .sum();
+        assert_eq!(id_sum, 8);
+    }
+
+    #[test]
+    fn part_two() {
+        let power_sum: u32 = EXAMPLE
+            .split('\n')
+            .map(|line| Game::parse(line).minimum_bag().power())
+            .sum();
+        assert_eq!(power_sum, 2286);
+    }
+}
@@ -94,3 +94,30 @@ pub fn main(input: &str) {
     println!("*  {}", schematic.part_number_sum);
     println!("** {}", schematic.gear_ratio_sum());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(Schematic::new(EXAMPLE).part_number_sum, 4361);
+    }
+
+    #[test]
+    fn part_two() {
+        assert_eq!(Schematic::new(EXAMPLE).gear_ratio_sum(), 467835);
+    }
+}
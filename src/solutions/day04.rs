@@ -1,5 +1,7 @@
 use std::collections::HashSet;
 
+use crate::utils::parse::tokens;
+
 struct Card {
     winning: HashSet<u32>,
     have: HashSet<u32>,
@@ -10,14 +12,8 @@ impl Card {
         let (_, post) = line.split_once(": ").expect("no colon");
         let (winning, have) = post.split_once(" | ").expect("no line");
         Card {
-            winning: winning
-                .split_whitespace()
-                .map(|x| x.parse().unwrap())
-                .collect(),
-            have: have
-                .split_whitespace()
-                .map(|x| x.parse().unwrap())
-                .collect(),
+            winning: tokens(winning, " ").unwrap().into_iter().collect(),
+            have: tokens(have, " ").unwrap().into_iter().collect(),
         }
     }
 
@@ -67,3 +63,26 @@ pub fn main(input: &str) {
     println!("*  {}", deck.point_total());
     println!("*  {}", deck.copy_total());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(Deck::parse(EXAMPLE).point_total(), 13);
+    }
+
+    #[test]
+    fn part_two() {
+        assert_eq!(Deck::parse(EXAMPLE).copy_total(), 30);
+    }
+}
@@ -1,5 +1,7 @@
-use gcollections::ops::*;
-use interval::interval_set::*;
+use crate::utils::{
+    interval::IntervalSet,
+    parse::{integers, key_values, paragraphs},
+};
 
 struct Conversion {
     destination_range_start: i64,
@@ -9,11 +11,14 @@ struct Conversion {
 
 impl Conversion {
     fn parse(line: &str) -> Self {
-        let mut nums = line.split(' ').map(|x| x.parse::<i64>().unwrap());
+        let [destination_range_start, source_range_start, range_length] = integers(line)
+            .expect("bad number")[..]
+            .try_into()
+            .expect("bad conversion");
         Self {
-            destination_range_start: nums.next().unwrap(),
-            source_range_start: nums.next().unwrap(),
-            range_length: nums.next().unwrap(),
+            destination_range_start,
+            source_range_start,
+            range_length,
         }
     }
 
@@ -30,12 +35,8 @@ impl Conversion {
     }
 
     // **
-    fn source_set(&self) -> IntervalSet<i64> {
-        vec![(
-            self.source_range_start,
-            self.source_range_start + self.range_length - 1,
-        )]
-        .to_interval_set()
+    fn source_set(&self) -> IntervalSet {
+        IntervalSet::from(self.source_range_start..self.source_range_start + self.range_length)
     }
 }
 
@@ -59,8 +60,8 @@ impl Map {
     }
 
     // **
-    fn convert_set(&self, mut set: IntervalSet<i64>) -> IntervalSet<i64> {
-        let mut mapped: IntervalSet<i64> = IntervalSet::empty();
+    fn convert_set(&self, mut set: IntervalSet) -> IntervalSet {
+        let mut mapped = IntervalSet::empty();
         for c in &self.conversions {
             let src = set.intersection(&c.source_set());
             set = set.difference(&src);
@@ -78,13 +79,8 @@ struct Almanac {
 
 impl Almanac {
     fn parse(input: &str) -> Self {
-        let mut paragraphs = input.split("\n\n");
-        let seed_paragraph = paragraphs.next().unwrap();
-        let seeds = seed_paragraph
-            .split(' ')
-            .skip(1)
-            .map(|x| x.parse::<i64>().unwrap())
-            .collect();
+        let mut paragraphs = paragraphs(input);
+        let (_seeds, seeds) = key_values(paragraphs.next().unwrap()).expect("bad seeds");
         let maps = paragraphs.map(Map::parse).collect();
         Self { seeds, maps }
     }
@@ -93,7 +89,7 @@ impl Almanac {
         self.maps.iter().fold(seed, |s, m| m.convert(s))
     }
 
-    fn convert_set(&self, set: IntervalSet<i64>) -> IntervalSet<i64> {
+    fn convert_set(&self, set: IntervalSet) -> IntervalSet {
         self.maps.iter().fold(set, |s, m| m.convert_set(s))
     }
 
@@ -102,15 +98,12 @@ impl Almanac {
     }
 
     // **
-    fn seed_set(&self) -> IntervalSet<i64> {
-        self.seeds
-            .chunks(2)
-            .map(|x| vec![(x[0], x[0] + x[1] - 1)].to_interval_set())
-            .fold(IntervalSet::empty(), |a, b| a.union(&b))
+    fn seed_set(&self) -> IntervalSet {
+        self.seeds.chunks(2).map(|x| x[0]..x[0] + x[1]).collect()
     }
 
     fn range_lowest_location(&self) -> i64 {
-        self.convert_set(self.seed_set()).lower()
+        self.convert_set(self.seed_set()).lower().unwrap()
     }
 }
 
@@ -119,3 +112,53 @@ pub fn main(input: &str) {
     println!("*  {:?}", almanac.lowest_location());
     println!("** {:?}", almanac.range_lowest_location());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(Almanac::parse(EXAMPLE).lowest_location(), 35);
+    }
+
+    #[test]
+    fn part_two() {
+        assert_eq!(Almanac::parse(EXAMPLE).range_lowest_location(), 46);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::utils::parse::key_values;
+
 struct Race {
     time: f64,
     record: f64,
@@ -43,11 +45,9 @@ impl RaceSheet {
     }
 
     fn parse_small(input: &str) -> Vec<Race> {
-        let mut lines = input.split('\n').map(|line| {
-            line.split_whitespace()
-                .skip(1)
-                .map(|x| x.parse::<f64>().unwrap())
-        });
+        let mut lines = input
+            .split('\n')
+            .map(|line| key_values::<f64>(line).expect("bad line").1.into_iter());
 
         let times = lines.next().unwrap();
         let records = lines.next().unwrap();
@@ -85,3 +85,22 @@ pub fn main(input: &str) {
     println!("*  {}", sheet.small_answer());
     println!("** {}", sheet.big_answer());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Time:      7  15   30
+Distance:  9  40  200";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(RaceSheet::parse(EXAMPLE).small_answer(), 288);
+    }
+
+    #[test]
+    fn part_two() {
+        assert_eq!(RaceSheet::parse(EXAMPLE).big_answer(), 71503);
+    }
+}
@@ -136,3 +136,25 @@ pub fn main(input: &str) {
     println!("*  {}", session.total_winnings(Mode::Jacks));
     println!("** {}", session.total_winnings(Mode::Jokers));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(Session::parse(EXAMPLE).total_winnings(Mode::Jacks), 6440);
+    }
+
+    #[test]
+    fn part_two() {
+        assert_eq!(Session::parse(EXAMPLE).total_winnings(Mode::Jokers), 5905);
+    }
+}
@@ -1,68 +1,101 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
 
-fn gcd(mut a: usize, mut b: usize) -> usize {
-    while b != 0 {
-        (a, b) = (b, a % b)
-    }
-    a
-}
+use crate::utils::{export, math::crt};
 
-fn lcm(a: usize, b: usize) -> usize {
-    a * b / gcd(a, b)
-}
+/// A node in the network, numbered in the order the input defines them.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct Node(usize);
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-struct Node(u32);
-
-impl Node {
-    fn parse(node: &str) -> Self {
-        let b = node.as_bytes();
-        Self(u32::from_be_bytes([0, b[0], b[1], b[2]]))
-    }
-
-    fn is_ghost_entrance(&self) -> bool {
-        self.0 % 256 == b'A' as u32
-    }
-
-    fn is_ghost_exit(&self) -> bool {
-        self.0 % 256 == b'Z' as u32
-    }
-}
+/// Fill colors for the ghosts' components in `Desert::dot`.
+const PALETTE: [&str; 6] = [
+    "lightblue",
+    "lightpink",
+    "palegreen",
+    "khaki",
+    "plum",
+    "lightsalmon",
+];
 
 struct Desert<'a> {
     instructions: &'a str,
-    network: HashMap<Node, (Node, Node)>,
+    names: Vec<&'a str>,
+    nodes: HashMap<&'a str, Node>,
+    /// The left and right neighbors of each node.
+    network: Vec<(Node, Node)>,
 }
 
+/// The shape of a ghost's walk: a tail leading into a loop that repeats
+/// forever, and the steps at which it's on a Z node in each part.
 #[derive(Debug)]
 struct Loop {
+    /// The first step of the first time around the loop.
+    loop_start: usize,
     loop_length: usize,
-    z_offsets: Vec<usize>,
+    /// Steps before `loop_start` where the ghost is on a Z node.
+    z_before_loop: Vec<usize>,
+    /// Steps in `loop_start..loop_start + loop_length` where the ghost is on a
+    /// Z node. These repeat every `loop_length` steps.
+    z_in_loop: Vec<usize>,
+}
+
+impl Loop {
+    fn is_on_z(&self, t: usize) -> bool {
+        if t < self.loop_start {
+            self.z_before_loop.contains(&t)
+        } else {
+            let t = self.loop_start + (t - self.loop_start) % self.loop_length;
+            self.z_in_loop.contains(&t)
+        }
+    }
 }
 
 impl<'a> Desert<'a> {
     fn parse(input: &'a str) -> Self {
         let (instructions, network) = input.split_once("\n\n").unwrap();
-        let network = network
+        // Split lines like `AAA = (BBB, CCC)` into their three names.
+        let lines: Vec<[&str; 3]> = network
             .split('\n')
             .map(|line| {
-                let src = Node::parse(&line[0..3]);
-                let l = Node::parse(&line[7..10]);
-                let r = Node::parse(&line[12..15]);
-                (src, (l, r))
+                let (src, dsts) = line.split_once('=').expect("no =");
+                let dsts = dsts.trim().trim_start_matches('(').trim_end_matches(')');
+                let (l, r) = dsts.split_once(',').expect("no comma");
+                [src.trim(), l.trim(), r.trim()]
             })
             .collect();
+        let names: Vec<&str> = lines.iter().map(|[src, _, _]| *src).collect();
+        let nodes: HashMap<&str, Node> = names
+            .iter()
+            .enumerate()
+            .map(|(i, &name)| (name, Node(i)))
+            .collect();
+        let node = |name| *nodes.get(name).expect("undefined node");
+        let network = lines.iter().map(|[_, l, r]| (node(l), node(r))).collect();
         Self {
-            instructions,
+            instructions: instructions.trim(),
+            names,
+            nodes,
             network,
         }
     }
 
+    fn node(&self, name: &str) -> Node {
+        self.nodes[name]
+    }
+
+    fn is_ghost_entrance(&self, node: Node) -> bool {
+        self.names[node.0].ends_with('A')
+    }
+
+    fn is_ghost_exit(&self, node: Node) -> bool {
+        self.names[node.0].ends_with('Z')
+    }
+
     fn step(&self, node: Node, direction: char) -> Node {
-        let (left, right) = self.network.get(&node).unwrap();
+        let (left, right) = self.network[node.0];
         match direction {
-            'L' => *left,
-            _ => *right,
+            'L' => left,
+            _ => right,
         }
     }
 
@@ -72,12 +105,15 @@ impl<'a> Desert<'a> {
         let mut z_offsets: Vec<usize> = vec![];
         for (i, c) in self.instructions.chars().cycle().enumerate() {
             if let Some(j) = seen.insert((node, i % self.instructions.len()), i) {
+                let (z_before_loop, z_in_loop) = z_offsets.iter().partition(|&&t| t < j);
                 return Loop {
+                    loop_start: j,
                     loop_length: i - j,
-                    z_offsets,
+                    z_before_loop,
+                    z_in_loop,
                 };
             }
-            if node.is_ghost_exit() {
+            if self.is_ghost_exit(node) {
                 z_offsets.push(i)
             }
             node = self.step(node, c);
@@ -85,9 +121,62 @@ impl<'a> Desert<'a> {
         unreachable!()
     }
 
+    /// Draw the network as a Graphviz digraph. Entrances are drawn as
+    /// houses and exits as double circles. With `color_components`, nodes
+    /// are filled with a color per ghost that can reach them (the first ghost
+    /// wins, if several can).
+    fn dot(&self, color_components: bool) -> String {
+        let nodes = (0..self.names.len()).map(Node);
+
+        let mut colors: Vec<Option<&str>> = vec![None; self.names.len()];
+        if color_components {
+            let entrances = nodes.clone().filter(|&n| self.is_ghost_entrance(n));
+            for (entrance, color) in entrances.zip(PALETTE.iter().cycle()) {
+                let mut queue = VecDeque::from([entrance]);
+                while let Some(node) = queue.pop_front() {
+                    if colors[node.0].is_some() {
+                        continue;
+                    }
+                    colors[node.0] = Some(color);
+                    let (l, r) = self.network[node.0];
+                    queue.extend([l, r]);
+                }
+            }
+        }
+
+        let name = |node: Node| export::dot_id(self.names[node.0]);
+        let mut out = String::from("digraph desert {\n");
+        for node in nodes.clone() {
+            let mut attributes = vec![];
+            if self.is_ghost_entrance(node) {
+                attributes.push("shape=house".to_owned());
+            } else if self.is_ghost_exit(node) {
+                attributes.push("shape=doublecircle".to_owned());
+            }
+            if let Some(color) = colors[node.0] {
+                attributes.push(format!("style=filled, fillcolor={color}"));
+            }
+            if !attributes.is_empty() {
+                writeln!(out, "    {} [{}];", name(node), attributes.join(", ")).unwrap();
+            }
+        }
+        for node in nodes {
+            let (l, r) = self.network[node.0];
+            let (node, l, r) = (name(node), name(l), name(r));
+            if l == r {
+                writeln!(out, "    {node} -> {l} [label=\"LR\"];").unwrap();
+            } else {
+                writeln!(out, "    {node} -> {l} [label=\"L\"];").unwrap();
+                writeln!(out, "    {node} -> {r} [label=\"R\"];").unwrap();
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
     fn people_steps(&self) -> usize {
-        let mut node = Node::parse("AAA");
-        let end = Node::parse("ZZZ");
+        let mut node = self.node("AAA");
+        let end = self.node("ZZZ");
         for (i, c) in self.instructions.chars().cycle().enumerate() {
             if node == end {
                 return i;
@@ -98,24 +187,201 @@ impl<'a> Desert<'a> {
     }
 
     fn ghost_steps(&self) -> usize {
-        let loops = self
-            .network
-            .keys()
-            .filter(|x| x.is_ghost_entrance())
-            .map(|x| self.find_loop(*x));
-
-        loops.fold(1, |acc, l| {
-            // This happens to be true... and means we can just lcm the numbers
-            // together intead of doing, idk, some chinese remainder theorem
-            // thing?
-            assert_eq!(l.z_offsets, vec![l.loop_length]);
-            lcm(acc, l.loop_length)
-        })
+        let loops: Vec<Loop> = (0..self.names.len())
+            .map(Node)
+            .filter(|&n| self.is_ghost_entrance(n))
+            .map(|n| self.find_loop(n))
+            .collect();
+        // Before every ghost is looping, the answer has to be one of the Z
+        // steps in the tail of the ghost that takes longest to start looping.
+        let settled = loops.iter().map(|l| l.loop_start).max().unwrap_or(0);
+        if let Some(l) = loops.iter().find(|l| l.loop_start == settled) {
+            if let Some(&t) = l
+                .z_before_loop
+                .iter()
+                .find(|&&t| loops.iter().all(|l| l.is_on_z(t)))
+            {
+                return t;
+            }
+        }
+
+        // After that, each ghost is on a Z node at some offset modulo its loop
+        // length. Try every combination of offsets and take the earliest.
+        let mut combinations: Vec<Vec<(i128, i128)>> = vec![vec![]];
+        for l in &loops {
+            let n = l.loop_length as i128;
+            combinations = combinations
+                .into_iter()
+                .flat_map(|c| {
+                    l.z_in_loop
+                        .iter()
+                        .map(move |&t| [&c[..], &[(t as i128 % n, n)]].concat())
+                })
+                .collect();
+        }
+        combinations
+            .iter()
+            .filter_map(|c| crt(c))
+            .map(|(x, m)| {
+                // Bump x up to the first solution where everyone is looping.
+                let behind = (settled as i128 - x).max(0);
+                (x + (behind + m - 1) / m * m) as usize
+            })
+            .min()
+            .expect("the ghosts are never all on Z nodes at once")
     }
 }
 
 pub fn main(input: &str) {
     let desert = Desert::parse(input);
+    export::write("desert.dot", || desert.dot(true));
     println!("*  {}", desert.people_steps());
     println!("** {}", desert.ghost_steps());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+RL
+
+AAA = (BBB, CCC)
+BBB = (DDD, EEE)
+CCC = (ZZZ, GGG)
+DDD = (DDD, DDD)
+EEE = (EEE, EEE)
+GGG = (GGG, GGG)
+ZZZ = (ZZZ, ZZZ)";
+
+    const REPEATING_EXAMPLE: &str = "\
+LLR
+
+AAA = (BBB, BBB)
+BBB = (AAA, ZZZ)
+ZZZ = (ZZZ, ZZZ)";
+
+    const GHOST_EXAMPLE: &str = "\
+LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)";
+
+    /// A ghost that passes a Z node once before it starts looping.
+    const GHOST_TAIL: &str = "
+22A = (22Z, 22Z)
+22Z = (22B, 22B)
+22B = (22C, 22C)
+22C = (33Z, 33Z)
+33Z = (22B, 22B)";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(Desert::parse(EXAMPLE).people_steps(), 2);
+        assert_eq!(Desert::parse(REPEATING_EXAMPLE).people_steps(), 6);
+    }
+
+    #[test]
+    fn part_two() {
+        assert_eq!(Desert::parse(GHOST_EXAMPLE).ghost_steps(), 6);
+        // Here the only ghost reaches ZZZ and stays there, so it's on a Z node
+        // twice per loop.
+        assert_eq!(Desert::parse(EXAMPLE).ghost_steps(), 2);
+        assert_eq!(Desert::parse(REPEATING_EXAMPLE).ghost_steps(), 6);
+    }
+
+    #[test]
+    fn long_names() {
+        let desert = Desert::parse(
+            "\
+LLR
+
+AAA=(B,B)
+B   =  ( AAA ,ZZZ )
+ZZZ = (ZZZ, ZZZ)",
+        );
+        assert_eq!(desert.people_steps(), 6);
+        let desert = Desert::parse(
+            "\
+LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+long_A = (22B, XXX)
+22B = (22C, 22C)
+22C = (long_name_Z, long_name_Z)
+long_name_Z = (22B, 22B)
+XXX = (XXX, XXX)",
+        );
+        assert_eq!(desert.ghost_steps(), 6);
+    }
+
+    #[test]
+    fn dot() {
+        let desert = Desert::parse(REPEATING_EXAMPLE);
+        let expected = "\
+digraph desert {
+    \"AAA\" [shape=house];
+    \"ZZZ\" [shape=doublecircle];
+    \"AAA\" -> \"BBB\" [label=\"LR\"];
+    \"BBB\" -> \"AAA\" [label=\"L\"];
+    \"BBB\" -> \"ZZZ\" [label=\"R\"];
+    \"ZZZ\" -> \"ZZZ\" [label=\"LR\"];
+}
+";
+        assert_eq!(desert.dot(false), expected);
+        let colored = desert.dot(true);
+        assert!(colored.contains("\"AAA\" [shape=house, style=filled, fillcolor=lightblue];"));
+        assert!(colored.contains("\"BBB\" [style=filled, fillcolor=lightblue];"));
+
+        let desert = Desert::parse("L\n\nA\"A = (Z\\Z, Z\\Z)\nZ\\Z = (Z\\Z, Z\\Z)");
+        assert!(desert
+            .dot(false)
+            .contains(r#""A\"A" -> "Z\\Z" [label="LR"];"#));
+    }
+
+    #[test]
+    fn find_loop() {
+        // 22A, 22Z, then 22B 22C 33Z forever.
+        let input = "L\n".to_owned() + GHOST_TAIL;
+        let desert = Desert::parse(&input);
+        let l = desert.find_loop(desert.node("22A"));
+        assert_eq!((l.loop_start, l.loop_length), (2, 3));
+        assert_eq!(l.z_before_loop, vec![1]);
+        assert_eq!(l.z_in_loop, vec![4]);
+        let on_z: Vec<usize> = (0..12).filter(|&t| l.is_on_z(t)).collect();
+        assert_eq!(on_z, vec![1, 4, 7, 10]);
+    }
+
+    #[test]
+    fn ghosts_out_of_step() {
+        // Ghost 2 is on a Z node once on its way in (at 1), then at 4, 7, 10,
+        // ... once it starts looping. Ghost 1 is on 11Z at 1, 3, 5, ...
+        let odd = "\
+L
+
+11A = (11Z, 11Z)
+11Z = (11B, 11B)
+11B = (11Z, 11Z)"
+            .to_owned()
+            + GHOST_TAIL;
+        assert_eq!(Desert::parse(&odd).ghost_steps(), 1);
+        // Ghost 1 is on 11Z at 2, 4, 6, ...
+        let even = "\
+L
+
+11A = (11B, 11B)
+11B = (11Z, 11Z)
+11Z = (11B, 11B)"
+            .to_owned()
+            + GHOST_TAIL;
+        assert_eq!(Desert::parse(&even).ghost_steps(), 4);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::utils::parse::tokens;
+
 struct Extrapolation {
     next: i32,
     previous: i32,
@@ -20,14 +22,36 @@ pub fn main(input: &str) {
     let mut total_next = 0;
     let mut total_previous = 0;
     for line in input.split('\n') {
-        let x = extrapolate(
-            line.split_whitespace()
-                .map(|x| x.parse::<i32>().unwrap())
-                .collect(),
-        );
+        let x = extrapolate(tokens(line, " ").expect("bad history"));
         total_next += x.next;
         total_previous += x.previous;
     }
     println!("*  {}", total_next);
     println!("** {}", total_previous);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+0 3 6 9 12 15
+1 3 6 10 15 21
+10 13 16 21 30 45";
+
+    fn extrapolations() -> impl Iterator<Item = Extrapolation> {
+        EXAMPLE
+            .split('\n')
+            .map(|line| extrapolate(tokens(line, " ").unwrap()))
+    }
+
+    #[test]
+    fn part_one() {
+        assert_eq!(extrapolations().map(|x| x.next).sum::<i32>(), 114);
+    }
+
+    #[test]
+    fn part_two() {
+        assert_eq!(extrapolations().map(|x| x.previous).sum::<i32>(), 2);
+    }
+}
@@ -1,6 +1,4 @@
-use std::collections::HashSet;
-
-use crate::utils::grid::Grid;
+use crate::utils::{geometry::Polygon, grid::Grid};
 
 pub struct Tile {
     directions: &'static [(i64, i64)],
@@ -40,58 +38,108 @@ impl Maze {
         }
     }
 
-    fn main_loop_and_distance(&self) -> (HashSet<(i64, i64)>, usize) {
+    /// Walk the loop through `S`, returning its cells in order.
+    fn main_loop(&self) -> Vec<(i64, i64)> {
         let start = self.grid.byte_position(b'S').unwrap();
-        let mut visited: HashSet<(i64, i64)> = HashSet::new();
-        let mut frontier = vec![start];
-        let mut distance = 0;
-
-        while !frontier.is_empty() {
-            let mut new_frontier = vec![];
-            for (x, y) in frontier {
-                visited.insert((x, y));
-                let tile = self.grid.get(x, y);
-                for (dx, dy) in tile.directions {
-                    if self.grid.in_range(x + dx, y + dy) {
-                        let other_tile = self.grid.get(x + dx, y + dy);
-                        if other_tile.directions.contains(&(-dx, -dy))
-                            && !visited.contains(&(x + dx, y + dy))
-                        {
-                            new_frontier.push((x + dx, y + dy));
-                        }
-                    }
-                }
-            }
-            frontier = new_frontier;
-            distance += 1;
+        let connects = |(x, y): (i64, i64), (dx, dy): (i64, i64)| {
+            self.grid.in_range(x + dx, y + dy)
+                && self
+                    .grid
+                    .get(x + dx, y + dy)
+                    .directions
+                    .contains(&(-dx, -dy))
+        };
+        let mut direction = *self
+            .grid
+            .get(start.0, start.1)
+            .directions
+            .iter()
+            .find(|&&d| connects(start, d))
+            .expect("S isn't connected to anything");
+
+        let mut path = vec![start];
+        let (mut x, mut y) = (start.0 + direction.0, start.1 + direction.1);
+        while (x, y) != start {
+            path.push((x, y));
+            let back = (-direction.0, -direction.1);
+            direction = *self
+                .grid
+                .get(x, y)
+                .directions
+                .iter()
+                .find(|&&d| d != back)
+                .unwrap();
+            (x, y) = (x + direction.0, y + direction.1);
         }
-        (visited, distance - 1)
+        path
     }
 
-    fn enclosed_area(&self, path: HashSet<(i64, i64)>) -> usize {
-        let mut area = 0;
-
-        // Basically https://en.wikipedia.org/wiki/Point_in_polygon#Ray_casting_algorithm
-        for y in 0..self.grid.height() as i64 {
-            let mut inside = false;
-            for x in 0..self.grid.width() as i64 {
-                if path.contains(&(x, y)) {
-                    let tile = self.grid.get(x, y);
-                    if tile.directions.contains(&NORTH) {
-                        inside = !inside;
-                    }
-                } else if inside {
-                    area += 1
-                }
-            }
-        }
-        area
+    fn enclosed_area(&self, path: Vec<(i64, i64)>) -> i64 {
+        // Every cell on the loop is a lattice point on the boundary, so Pick's
+        // theorem tells us how many cells lie inside.
+        Polygon::new(path).interior_points()
     }
 }
 
 pub fn main(input: &str) {
     let maze = Maze::parse(input);
-    let (main_loop, diameter) = maze.main_loop_and_distance();
-    println!("*  {}", diameter);
+    let main_loop = maze.main_loop();
+    println!("*  {}", main_loop.len() / 2);
     println!("** {}", maze.enclosed_area(main_loop))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE_EXAMPLE: &str = "\
+.....
+.S-7.
+.|.|.
+.L-J.
+.....";
+
+    const COMPLEX_EXAMPLE: &str = "\
+..F7.
+.FJ|.
+SJ.L7
+|F--J
+LJ...";
+
+    const SQUEEZE_EXAMPLE: &str = "\
+...........
+.S-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--J.
+...........";
+
+    const JUNK_EXAMPLE: &str = "\
+FF7FSF7F7F7F7F7F---7
+L|LJ||||||||||||F--J
+FL-7LJLJ||||||LJL-77
+F--JF--7||LJLJ7F7FJ-
+L---JF-JLJ.||-FJLJJ7
+|F|F-JF---7F7-L7L|7|
+|FFJF7L7F-JF7|JL---7
+7-L-JL7||F7|L7F-7F7|
+L.L7LFJ|||||FJL7||LJ
+L7JLJL-JLJLJL--JLJ.L";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(Maze::parse(SQUARE_EXAMPLE).main_loop().len() / 2, 4);
+        assert_eq!(Maze::parse(COMPLEX_EXAMPLE).main_loop().len() / 2, 8);
+    }
+
+    #[test]
+    fn part_two() {
+        for (example, area) in [(SQUEEZE_EXAMPLE, 4), (JUNK_EXAMPLE, 10)] {
+            let maze = Maze::parse(example);
+            assert_eq!(maze.enclosed_area(maze.main_loop()), area);
+        }
+    }
+}
@@ -86,3 +86,34 @@ pub fn main(input: &str) {
     universe.expansion_factor = 1_000_000;
     println!("** {}", universe.shortest_path_sum());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+...#......
+.......#..
+#.........
+..........
+......#...
+.#........
+.........#
+..........
+.......#..
+#...#.....";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(Universe::parse(EXAMPLE).shortest_path_sum(), 374);
+    }
+
+    #[test]
+    fn part_two() {
+        let mut universe = Universe::parse(EXAMPLE);
+        universe.expansion_factor = 10;
+        assert_eq!(universe.shortest_path_sum(), 1030);
+        universe.expansion_factor = 100;
+        assert_eq!(universe.shortest_path_sum(), 8410);
+    }
+}
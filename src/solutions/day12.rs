@@ -1,72 +1,97 @@
-struct Configurations<'a> {
-    template: &'a str,
-    buffer: Vec<bool>,
-    yielded_first: bool,
-}
-
-impl<'a> Configurations<'a> {
-    fn new(template: &'a str) -> Self {
-        Self {
-            template,
-            buffer: template.bytes().map(|b| b == b'#').collect(),
-            yielded_first: false,
-        }
-    }
-}
+use crate::utils::parse::tokens;
 
-impl<'a> Iterator for Configurations<'a> {
-    type Item = Vec<bool>;
+/// Count the ways to fill in the `?`s in `pattern` so that its runs of `#`
+/// have the lengths in `groups`.
+///
+/// We scan the pattern left to right, keeping track of how many ways there are
+/// to have completed `g` groups and be `r` springs into the next one.
+fn count_configurations(pattern: &str, groups: &[usize]) -> usize {
+    let max_run = groups.iter().copied().max().unwrap_or(0);
+    let mut ways = vec![vec![0; max_run + 1]; groups.len() + 1];
+    ways[0][0] = 1;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if !self.yielded_first {
-            self.yielded_first = true;
-            return Some(self.buffer.clone());
-        }
-        for (i, c) in self.template.bytes().enumerate() {
-            if c == b'?' {
-                if self.buffer[i] {
-                    self.buffer[i] = false;
-                } else {
-                    self.buffer[i] = true;
-                    return Some(self.buffer.clone());
+    for c in pattern.bytes() {
+        let mut next = vec![vec![0; max_run + 1]; groups.len() + 1];
+        for g in 0..=groups.len() {
+            for r in 0..=max_run {
+                let n = ways[g][r];
+                if n == 0 {
+                    continue;
+                }
+                // A damaged spring extends the current run, if there's room.
+                if c != b'.' && g < groups.len() && r < groups[g] {
+                    next[g][r + 1] += n;
+                }
+                // An operational spring ends the current run, if it's complete.
+                if c != b'#' {
+                    if r == 0 {
+                        next[g][0] += n;
+                    } else if r == groups[g] {
+                        next[g + 1][0] += n;
+                    }
                 }
             }
         }
-        None
+        ways = next;
     }
-}
 
-fn matches(config: &[bool], nums: &[usize]) -> bool {
-    config
-        .split(|b| !b)
-        .map(|x| x.len())
-        .filter(|x| *x > 0)
-        .eq(nums.iter().copied())
+    let done = groups.len();
+    let finishing = groups.last().map_or(0, |&last| ways[done - 1][last]);
+    ways[done][0] + finishing
 }
 
-fn count_configurations(pattern: &str, nums: &[usize]) -> usize {
-    let mut count = 0;
-    for config in Configurations::new(pattern) {
-        if matches(&config, nums) {
-            count += 1;
-        }
-    }
-    count
+fn unfold(pattern: &str, groups: &[usize]) -> (String, Vec<usize>) {
+    ([pattern; 5].join("?"), groups.repeat(5))
 }
 
 pub fn main(input: &str) {
     let mut sum = 0;
-    // let mut sum2 = 0;
+    let mut sum2 = 0;
     for line in input.split('\n') {
         let (pattern, nums) = line.split_once(' ').unwrap();
-        let nums: Vec<usize> = nums.split(',').map(|x| x.parse().unwrap()).collect();
-        let n = count_configurations(pattern, &nums);
-        sum += n;
-        // let unfolded = format!("{pattern}?{pattern}?{pattern}?{pattern}?{pattern}");
-        // let n2 = count_configurations(&unfolded, &nums.repeat(5));
-        // sum2 += n2;
-        // println!("{n2}");
+        let nums: Vec<usize> = tokens(nums, ",").expect("bad groups");
+        sum += count_configurations(pattern, &nums);
+        let (unfolded, unfolded_nums) = unfold(pattern, &nums);
+        sum2 += count_configurations(&unfolded, &unfolded_nums);
     }
     println!("*  {sum}");
-    // println!("** {sum2}");
+    println!("** {sum2}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+???.### 1,1,3
+.??..??...?##. 1,1,3
+?#?#?#?#?#?#?#? 1,3,1,6
+????.#...#... 4,1,1
+????.######..#####. 1,6,5
+?###???????? 3,2,1";
+
+    #[test]
+    fn part_one() {
+        let counts: Vec<usize> = EXAMPLE
+            .split('\n')
+            .map(|line| {
+                let (pattern, nums) = line.split_once(' ').unwrap();
+                count_configurations(pattern, &tokens(nums, ",").unwrap())
+            })
+            .collect();
+        assert_eq!(counts, [1, 4, 1, 1, 4, 10]);
+    }
+
+    #[test]
+    fn part_two() {
+        let counts: Vec<usize> = EXAMPLE
+            .split('\n')
+            .map(|line| {
+                let (pattern, nums) = line.split_once(' ').unwrap();
+                let (pattern, nums) = unfold(pattern, &tokens(nums, ",").unwrap());
+                count_configurations(&pattern, &nums)
+            })
+            .collect();
+        assert_eq!(counts, [1, 16384, 1, 16, 2500, 506250]);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::utils::grid::Grid;
+use crate::utils::{grid::Grid, parse::paragraphs};
 
 /// Is there a symmetry axis after the leftmost n columns?
 fn column_symmetric(grid: &Grid<u8>, n: usize) -> bool {
@@ -53,7 +53,7 @@ fn alternate_score(grid: &mut Grid<u8>) -> usize {
 pub fn main(input: &str) {
     let mut score = 0;
     let mut alt_score = 0;
-    for paragraph in input.split("\n\n") {
+    for paragraph in paragraphs(input) {
         let mut grid = Grid::<u8>::parse(paragraph);
         score += symmetry_score(&grid, None).unwrap();
         alt_score += alternate_score(&mut grid);
@@ -61,3 +61,41 @@ pub fn main(input: &str) {
     println!("*  {score}");
     println!("** {alt_score}");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+#.##..##.
+..#.##.#.
+##......#
+##......#
+..#.##.#.
+..##..##.
+#.##..##.
+
+#...##..#
+#....#..#
+..##..###
+#####.##.
+#####.##.
+..##..###
+#....#..#";
+
+    #[test]
+    fn part_one() {
+        let scores: Vec<usize> = paragraphs(EXAMPLE)
+            .map(|p| symmetry_score(&Grid::parse(p), None).unwrap())
+            .collect();
+        assert_eq!(scores, [5, 400]);
+    }
+
+    #[test]
+    fn part_two() {
+        let scores: Vec<usize> = paragraphs(EXAMPLE)
+            .map(|p| alternate_score(&mut Grid::parse(p)))
+            .collect();
+        assert_eq!(scores, [300, 100]);
+    }
+}
@@ -97,28 +97,60 @@ fn spin_cycle(grid: &mut Grid<Tile>) -> usize {
     roll_east(grid)
 }
 
-pub fn main(input: &str) {
-    let mut grid = Grid::<Tile>::parse(input);
-    let load = roll_north(&mut grid);
-    println!("*  {}", load);
-
-    // Finish the first spin cycle
-    roll_west(&mut grid);
-    roll_south(&mut grid);
-    roll_east(&mut grid);
-
+/// Find the north load after running `cycles` spin cycles, by looking for the
+/// point where the grid starts repeating.
+fn load_after_spin_cycles(mut grid: Grid<Tile>, cycles: usize) -> usize {
     let mut seen = HashMap::<Grid<Tile>, usize>::new();
     let mut loads = HashMap::<usize, usize>::new();
-    for i in 2.. {
+    for i in 1.. {
         let load = spin_cycle(&mut grid);
         loads.insert(i, load);
+        if i == cycles {
+            return load;
+        }
         if let Some(past) = seen.insert(grid.clone(), i) {
             let period = i - past;
             // Find the load for the time t within the period that's equal to
-            // 1_000_000_000 modulo the period.
-            let t = past + (1_000_000_000 - past) % period;
-            println!("** {}", loads.get(&t).unwrap());
-            break;
+            // the cycle count modulo the period.
+            let t = past + (cycles - past) % period;
+            return *loads.get(&t).unwrap();
         }
     }
+    unreachable!()
+}
+
+pub fn main(input: &str) {
+    let grid = Grid::<Tile>::parse(input);
+    println!("*  {}", roll_north(&mut grid.clone()));
+    println!("** {}", load_after_spin_cycles(grid, 1_000_000_000));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+O....#....
+O.OO#....#
+.....##...
+OO.#O....O
+.O.....O#.
+O.#..O.#.#
+..O..#O..O
+.......O..
+#....###..
+#OO..#....";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(roll_north(&mut Grid::parse(EXAMPLE)), 136);
+    }
+
+    #[test]
+    fn part_two() {
+        assert_eq!(
+            load_after_spin_cycles(Grid::parse(EXAMPLE), 1_000_000_000),
+            64
+        );
+    }
 }
@@ -94,3 +94,25 @@ pub fn main(input: &str) {
     println!("*  {total}");
     println!("** {}", lens_map.focusing_power());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(hash("HASH"), 52);
+        assert_eq!(EXAMPLE.split(',').map(hash).sum::<usize>(), 1320);
+    }
+
+    #[test]
+    fn part_two() {
+        let mut lens_map = LensMap::new();
+        for step in EXAMPLE.split(',') {
+            lens_map.execute(Step::parse(step));
+        }
+        assert_eq!(lens_map.focusing_power(), 145);
+    }
+}
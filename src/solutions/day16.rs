@@ -1,70 +1,430 @@
-use std::collections::HashMap;
+use std::fmt;
 
-use crate::utils::grid::Grid;
+use pathfinding::prelude::strongly_connected_components;
 
-pub fn light(
-    grid: &Grid<u8>,
-    energized: &mut HashMap<(i64, i64), u8>,
+use crate::utils::{export, grid::Grid};
+
+/// A beam of light entering cell `(x, y)`, heading in direction `(dx, dy)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Beam {
     x: i64,
     y: i64,
     dx: i64,
     dy: i64,
-) {
-    if !grid.in_range(x, y) {
-        return;
+}
+
+impl Beam {
+    fn new(x: i64, y: i64, dx: i64, dy: i64) -> Self {
+        Self { x, y, dx, dy }
+    }
+}
+
+impl fmt::Display for Beam {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let heading = match (self.dx, self.dy) {
+            (1, 0) => "right",
+            (-1, 0) => "left",
+            (0, 1) => "down",
+            _ => "up",
+        };
+        write!(f, "({}, {}) heading {heading}", self.x, self.y)
+    }
+}
+
+/// A straight stretch of beam, from the first cell it lights up to the last,
+/// before it turns, splits, leaves the grid or runs into light that's already
+/// been traced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Segment {
+    start: (i64, i64),
+    end: (i64, i64),
+    direction: (i64, i64),
+}
+
+impl Segment {
+    fn cells(&self) -> impl Iterator<Item = (i64, i64)> {
+        let (dx, dy) = self.direction;
+        let n = self.start.0.abs_diff(self.end.0) + self.start.1.abs_diff(self.end.1);
+        let (x, y) = self.start;
+        (0..=n as i64).map(move |i| (x + i * dx, y + i * dy))
     }
+}
 
-    let bit = match (dx, dy) {
+fn direction_bit(dx: i64, dy: i64) -> u8 {
+    match (dx, dy) {
         (1, 0) => 1,
         (-1, 0) => 2,
         (0, 1) => 4,
         (0, -1) => 8,
-        _ => panic!(),
-    };
-    let old = energized.get(&(x, y)).unwrap_or(&0);
-    if bit & old != 0 {
-        return;
+        _ => panic!("bad direction ({dx}, {dy})"),
+    }
+}
+
+struct Trace {
+    /// For each cell, a bitmask of the directions light has passed through it.
+    visited: Vec<u8>,
+    segments: Vec<Segment>,
+}
+
+impl Trace {
+    fn energized(&self) -> usize {
+        self.visited.iter().filter(|&&bits| bits != 0).count()
     }
-    energized.insert((x, y), bit | old);
 
-    match grid.get(x, y) {
-        b'|' if dx != 0 => {
-            light(grid, energized, x, y - 1, 0, -1);
-            light(grid, energized, x, y + 1, 0, 1);
+    /// Draw the beams over the empty cells of the grid, like the puzzle text
+    /// does: an arrow where light passes one way, or how many ways it passes.
+    fn render(&self, grid: &Grid<u8>) -> String {
+        let mut canvas = grid.clone();
+        let mut bits = vec![0u8; grid.width() * grid.height()];
+        for segment in &self.segments {
+            let (dx, dy) = segment.direction;
+            for (x, y) in segment.cells() {
+                bits[y as usize * grid.width() + x as usize] |= direction_bit(dx, dy);
+            }
         }
-        b'-' if dy != 0 => {
-            light(grid, energized, x - 1, y, -1, 0);
-            light(grid, energized, x + 1, y, 1, 0);
+        for y in 0..grid.height() as i64 {
+            for x in 0..grid.width() as i64 {
+                let b = bits[y as usize * grid.width() + x as usize];
+                if grid.get(x, y) != b'.' || b == 0 {
+                    continue;
+                }
+                let c = match b {
+                    1 => b'>',
+                    2 => b'<',
+                    4 => b'v',
+                    8 => b'^',
+                    _ => b'0' + b.count_ones() as u8,
+                };
+                canvas.set(x, y, c);
+            }
         }
-        b'/' => light(grid, energized, x - dy, y - dx, -dy, -dx),
-        b'\\' => light(grid, energized, x + dy, y + dx, dy, dx),
-        _ => light(grid, energized, x + dx, y + dy, dx, dy),
+        canvas.string().to_owned()
     }
 }
 
+/// Follow a beam and everything it splits into, with an explicit worklist
+/// instead of recursion so that long beams can't overflow the stack.
+fn trace(grid: &Grid<u8>, start: Beam) -> Trace {
+    let width = grid.width();
+    let mut visited = vec![0u8; width * grid.height()];
+    let mut segments = vec![];
+    let mut worklist = vec![start];
+
+    while let Some(beam) = worklist.pop() {
+        let (mut x, mut y, dx, dy) = (beam.x, beam.y, beam.dx, beam.dy);
+        let first = (x, y);
+        let mut last = None;
+        while grid.in_range(x, y) {
+            let i = y as usize * width + x as usize;
+            let bit = direction_bit(dx, dy);
+            if visited[i] & bit != 0 {
+                break;
+            }
+            visited[i] |= bit;
+            last = Some((x, y));
+
+            let mut turn = |dx, dy| worklist.push(Beam::new(x + dx, y + dy, dx, dy));
+            match grid.get(x, y) {
+                b'|' if dx != 0 => {
+                    turn(0, -1);
+                    turn(0, 1);
+                }
+                b'-' if dy != 0 => {
+                    turn(-1, 0);
+                    turn(1, 0);
+                }
+                b'/' => turn(-dy, -dx),
+                b'\\' => turn(dy, dx),
+                _ => {
+                    x += dx;
+                    y += dy;
+                    continue;
+                }
+            }
+            break;
+        }
+        if let Some(end) = last {
+            segments.push(Segment {
+                start: first,
+                end,
+                direction: (dx, dy),
+            });
+        }
+    }
+
+    Trace { visited, segments }
+}
+
 fn count_energized(grid: &Grid<u8>, x: i64, y: i64, dx: i64, dy: i64) -> usize {
-    let mut energized: HashMap<(i64, i64), u8> = HashMap::new();
-    light(&grid, &mut energized, x, y, dx, dy);
-    energized.len()
+    trace(grid, Beam::new(x, y, dx, dy)).energized()
+}
+
+/// A set of cells, stored as a bitmap.
+#[derive(Clone)]
+struct CellSet(Vec<u64>);
+
+impl CellSet {
+    fn new(cells: usize) -> Self {
+        Self(vec![0; cells.div_ceil(64)])
+    }
+
+    fn insert(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    fn union_with(&mut self, other: &CellSet) {
+        for (a, b) in self.0.iter_mut().zip(&other.0) {
+            *a |= b;
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.0.iter().map(|w| w.count_ones() as usize).sum()
+    }
+}
+
+/// Most entry beams end up hitting the same splitters, so instead of tracing
+/// each one from scratch, we work out what lights up downstream of every
+/// splitter once.
+///
+/// Each splitter is a node, with edges to the splitters its two outgoing beams
+/// run into (past any mirrors). Splitters that feed each other in a loop light
+/// up the same tiles, so we condense those into strongly connected components
+/// and compute one set of energized tiles per component.
+struct SplitterGraph<'a> {
+    grid: &'a Grid<u8>,
+    /// For each cell, the node index of the splitter there, if any.
+    node_at: Vec<Option<usize>>,
+    /// For each node, the component it's in.
+    component: Vec<usize>,
+    /// For each component, everything lit up once light reaches it.
+    energized: Vec<CellSet>,
+}
+
+impl<'a> SplitterGraph<'a> {
+    fn new(grid: &'a Grid<u8>) -> Self {
+        let width = grid.width();
+        let cells = width * grid.height();
+        let splitters: Vec<(i64, i64)> = grid
+            .byte_positions(b'|')
+            .chain(grid.byte_positions(b'-'))
+            .collect();
+        let mut node_at = vec![None; cells];
+        for (n, &(x, y)) in splitters.iter().enumerate() {
+            node_at[y as usize * width + x as usize] = Some(n);
+        }
+        let mut graph = Self {
+            grid,
+            node_at,
+            component: vec![0; splitters.len()],
+            energized: vec![],
+        };
+
+        // Follow both beams out of every splitter to see what they light up
+        // and where they end up.
+        let mut lit = vec![];
+        let mut successors = vec![];
+        for &(x, y) in &splitters {
+            let mut cells = CellSet::new(cells);
+            cells.insert(y as usize * width + x as usize);
+            let mut next = vec![];
+            for beam in graph.split(x, y) {
+                let (path, node) = graph.follow(beam);
+                for i in path {
+                    cells.insert(i);
+                }
+                next.extend(node);
+            }
+            lit.push(cells);
+            successors.push(next);
+        }
+
+        // The components come out in reverse topological order, so the ones
+        // downstream of each component are always done before it.
+        let nodes: Vec<usize> = (0..splitters.len()).collect();
+        let components = strongly_connected_components(&nodes, |&n| successors[n].clone());
+        let mut energized: Vec<Option<CellSet>> = vec![None; components.len()];
+        for (c, members) in components.iter().enumerate() {
+            for &n in members {
+                graph.component[n] = c;
+            }
+            let mut set = CellSet::new(cells);
+            for &n in members {
+                set.union_with(&lit[n]);
+                for &m in &successors[n] {
+                    if !members.contains(&m) {
+                        let downstream = energized[graph.component[m]].as_ref();
+                        set.union_with(downstream.expect("components out of order"));
+                    }
+                }
+            }
+            energized[c] = Some(set);
+        }
+        graph.energized = energized.into_iter().map(Option::unwrap).collect();
+        graph
+    }
+
+    /// The two beams leaving the splitter at `(x, y)` when it's hit side-on.
+    fn split(&self, x: i64, y: i64) -> [Beam; 2] {
+        match self.grid.get(x, y) {
+            b'|' => [Beam::new(x, y - 1, 0, -1), Beam::new(x, y + 1, 0, 1)],
+            _ => [Beam::new(x - 1, y, -1, 0), Beam::new(x + 1, y, 1, 0)],
+        }
+    }
+
+    /// Follow a beam through mirrors until it leaves the grid or hits a
+    /// splitter side-on. Returns the cells it lit on the way and the node it
+    /// hit, if any.
+    fn follow(&self, beam: Beam) -> (Vec<usize>, Option<usize>) {
+        let width = self.grid.width();
+        let Beam {
+            mut x,
+            mut y,
+            mut dx,
+            mut dy,
+        } = beam;
+        let mut path = vec![];
+        // A beam bouncing between mirrors forever has to repeat itself within
+        // this many steps.
+        for _ in 0..4 * width * self.grid.height() {
+            if !self.grid.in_range(x, y) {
+                break;
+            }
+            let i = y as usize * width + x as usize;
+            match self.grid.get(x, y) {
+                b'|' if dx != 0 => return (path, self.node_at[i]),
+                b'-' if dy != 0 => return (path, self.node_at[i]),
+                b'/' => (dx, dy) = (-dy, -dx),
+                b'\\' => (dx, dy) = (dy, dx),
+                _ => {}
+            }
+            path.push(i);
+            x += dx;
+            y += dy;
+        }
+        (path, None)
+    }
+
+    fn energized(&self, beam: Beam) -> usize {
+        let (path, node) = self.follow(beam);
+        let mut set = match node {
+            Some(n) => self.energized[self.component[n]].clone(),
+            None => CellSet::new(self.node_at.len()),
+        };
+        for i in path {
+            set.insert(i);
+        }
+        set.len()
+    }
 }
 
-fn most_energized(grid: &Grid<u8>) -> usize {
-    let mut best = 0;
+/// Every beam that could enter the grid from one of its edges.
+fn entry_beams(grid: &Grid<u8>) -> Vec<Beam> {
     let w = grid.width() as i64;
     let h = grid.height() as i64;
+    let mut beams = vec![];
     for x in 0..w {
-        best = best.max(count_energized(&grid, x, 0, 0, 1));
-        best = best.max(count_energized(&grid, x, h - 1, 0, -1));
+        beams.push(Beam::new(x, 0, 0, 1));
+        beams.push(Beam::new(x, h - 1, 0, -1));
     }
     for y in 0..h {
-        best = best.max(count_energized(&grid, 0, y, 1, 0));
-        best = best.max(count_energized(&grid, w - 1, y, -1, 0));
+        beams.push(Beam::new(0, y, 1, 0));
+        beams.push(Beam::new(w - 1, y, -1, 0));
     }
-    best
+    beams
+}
+
+/// Find the entry beam that energizes the most tiles, and how many it
+/// energizes, splitting the entries evenly across `threads` threads.
+fn most_energized(grid: &Grid<u8>, threads: usize) -> (Beam, usize) {
+    let graph = SplitterGraph::new(grid);
+    let best = |beams: &[Beam]| {
+        beams
+            .iter()
+            .map(|&beam| (beam, graph.energized(beam)))
+            .max_by_key(|&(_, count)| count)
+    };
+    let entries = entry_beams(grid);
+    if threads <= 1 {
+        return best(&entries).unwrap();
+    }
+    let chunk_size = entries.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = entries
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || best(chunk)))
+            .collect();
+        workers
+            .into_iter()
+            .filter_map(|worker| worker.join().unwrap())
+            .max_by_key(|&(_, count)| count)
+            .unwrap()
+    })
 }
 
 pub fn main(input: &str) {
     let grid = Grid::<u8>::parse(input);
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
     println!("*  {}", count_energized(&grid, 0, 0, 1, 0));
-    println!("** {}", most_energized(&grid));
+    export::write("beams.txt", || {
+        trace(&grid, Beam::new(0, 0, 1, 0)).render(&grid)
+    });
+    let (entry, energized) = most_energized(&grid, threads);
+    println!("** {energized} (entering at {entry})");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r".|...\....
+|.-.\.....
+.....|-...
+........|.
+..........
+.........\
+..../.\\..
+.-.-/..|..
+.|....-|.\
+..//.|....";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(count_energized(&Grid::parse(EXAMPLE), 0, 0, 1, 0), 46);
+    }
+
+    #[test]
+    fn part_two() {
+        let grid = Grid::parse(EXAMPLE);
+        let best = (Beam::new(3, 0, 0, 1), 51);
+        assert_eq!(most_energized(&grid, 1), best);
+        assert_eq!(most_energized(&grid, 4), best);
+        assert_eq!(best.0.to_string(), "(3, 0) heading down");
+    }
+
+    #[test]
+    fn splitter_graph() {
+        let grid = Grid::parse(EXAMPLE);
+        let graph = SplitterGraph::new(&grid);
+        for beam in entry_beams(&grid) {
+            assert_eq!(graph.energized(beam), trace(&grid, beam).energized());
+        }
+    }
+
+    #[test]
+    fn render() {
+        let grid = Grid::parse(EXAMPLE);
+        let trace = trace(&grid, Beam::new(0, 0, 1, 0));
+        let expected = r">|<<<\....
+|v-.\^....
+.v...|->>>
+.v...v^.|.
+.v...v^...
+.v...v^..\
+.v../2\\..
+<->-/vv|..
+.|<<<2-|.\
+.v//.|.v..";
+        assert_eq!(trace.render(&grid), expected);
+    }
 }
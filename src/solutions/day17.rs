@@ -1,4 +1,4 @@
-use crate::utils::grid::Grid;
+use crate::utils::{export, grid::Grid};
 use pathfinding::prelude::astar;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
@@ -10,23 +10,59 @@ struct State {
     steps: usize,
 }
 
+/// How a crucible moves, and where it's going.
+#[derive(Clone, Copy, Debug)]
+struct Crucible {
+    /// How far it has to go in a straight line before it can turn.
+    min_steps: usize,
+    /// How far it can go in a straight line before it has to turn.
+    max_steps: usize,
+    start: (i64, i64),
+    goal: (i64, i64),
+    /// Does it need to have gone `min_steps` in a straight line to stop?
+    stop_needs_min_run: bool,
+    can_reverse: bool,
+}
+
+impl Crucible {
+    /// A crucible going from the top left to the bottom right corner.
+    fn new(grid: &Grid<u8>, min_steps: usize, max_steps: usize) -> Self {
+        Self {
+            min_steps,
+            max_steps,
+            start: (0, 0),
+            goal: (grid.width() as i64 - 1, grid.height() as i64 - 1),
+            stop_needs_min_run: true,
+            can_reverse: false,
+        }
+    }
+
+    /// Are the start and goal both in the city?
+    fn fits(&self, grid: &Grid<u8>) -> bool {
+        let [(sx, sy), (gx, gy)] = [self.start, self.goal];
+        grid.in_range(sx, sy) && grid.in_range(gx, gy)
+    }
+}
+
 fn successors(
     grid: &Grid<u8>,
     previous: State,
-    min_steps: usize,
-    max_steps: usize,
+    crucible: Crucible,
 ) -> impl Iterator<Item = (State, usize)> + '_ {
     vec![(-1, 0), (1, 0), (0, -1), (0, 1)]
         .into_iter()
         .filter_map(move |(dx, dy)| {
             let x = previous.x + dx;
             let y = previous.y + dy;
+            if (dx, dy) == (-previous.dx, -previous.dy) && !crucible.can_reverse {
+                return None;
+            }
             let turn = (dx, dy) != (previous.dx, previous.dy);
-            if turn && previous.steps < min_steps {
+            if turn && previous.steps < crucible.min_steps {
                 return None;
             }
             let steps = if turn { 1 } else { previous.steps + 1 };
-            if steps > max_steps || !grid.in_range(x, y) {
+            if steps > crucible.max_steps || !grid.in_range(x, y) {
                 return None;
             }
             let state = State {
@@ -41,28 +77,328 @@ fn successors(
         })
 }
 
-fn heat_loss(grid: &Grid<u8>, min_steps: usize, max_steps: usize) -> usize {
-    let w = grid.width() as i64;
-    let h = grid.height() as i64;
+/// The cheapest way through the city, and how much heat it loses.
+struct Route {
+    states: Vec<State>,
+    heat_loss: usize,
+}
+
+impl Route {
+    /// Draw the route over the grid with arrows, like the puzzle text does.
+    fn render(&self, grid: &Grid<u8>) -> String {
+        let mut canvas = grid.clone();
+        for s in &self.states[1..] {
+            let arrow = match (s.dx, s.dy) {
+                (1, 0) => b'>',
+                (-1, 0) => b'<',
+                (0, 1) => b'v',
+                _ => b'^',
+            };
+            canvas.set(s.x, s.y, arrow);
+        }
+        canvas.string().to_owned()
+    }
+}
+
+/// The cheapest route for the crucible, or `None` if it can't get there.
+fn best_route(grid: &Grid<u8>, crucible: Crucible) -> Option<Route> {
+    if !crucible.fits(grid) {
+        return None;
+    }
+    let (gx, gy) = crucible.goal;
+    // Starting with a full run lets the crucible set off in any direction.
     let start = State {
-        x: 0,
-        y: 0,
+        x: crucible.start.0,
+        y: crucible.start.1,
         dx: 0,
         dy: 0,
-        steps: min_steps,
+        steps: crucible.min_steps,
     };
-    astar(
+    let (states, heat_loss) = astar(
         &start,
-        |s| successors(&grid, s.clone(), min_steps, max_steps),
-        |s| ((w - 1).abs_diff(s.x) + (h - 1).abs_diff(s.y)) as usize,
-        |s| (s.x, s.y) == (w - 1, h - 1),
-    )
-    .unwrap()
-    .1
+        |s| successors(grid, s.clone(), crucible),
+        |s| (gx.abs_diff(s.x) + gy.abs_diff(s.y)) as usize,
+        |s| {
+            (s.x, s.y) == crucible.goal
+                && (s.steps >= crucible.min_steps || !crucible.stop_needs_min_run)
+        },
+    )?;
+    Some(Route { states, heat_loss })
+}
+
+const DIRECTIONS: [(i64, i64); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+/// The "direction" of the crucible before it has moved at all.
+const STILL: usize = 4;
+
+/// The same search as `best_route`, specialized for single-digit weights: a
+/// state is packed into an index into a flat distance array, and the queue is
+/// a ring of buckets, one per distance mod 10.
+fn heat_loss_bucketed(grid: &Grid<u8>, crucible: Crucible) -> Option<usize> {
+    if !crucible.fits(grid) {
+        return None;
+    }
+    let w = grid.width() as i64;
+    let runs = crucible.max_steps + 1;
+    let index =
+        |x: i64, y: i64, d: usize, steps: usize| ((y * w + x) as usize * 5 + d) * runs + steps;
+    let mut dist = vec![usize::MAX; grid.width() * grid.height() * 5 * runs];
+    let mut buckets: Vec<Vec<usize>> = vec![vec![]; 10];
+
+    // Starting with a full run lets the crucible set off in any direction.
+    let (sx, sy) = crucible.start;
+    let i = index(sx, sy, STILL, crucible.min_steps.min(crucible.max_steps));
+    dist[i] = 0;
+    buckets[0].push(i);
+
+    let mut queued = 1;
+    let mut current = 0;
+    while queued > 0 {
+        let Some(i) = buckets[current % 10].pop() else {
+            current += 1;
+            continue;
+        };
+        queued -= 1;
+        if dist[i] != current {
+            continue;
+        }
+        let steps = i % runs;
+        let d = i / runs % 5;
+        let cell = (i / runs / 5) as i64;
+        let (x, y) = (cell % w, cell / w);
+        if (x, y) == crucible.goal && (steps >= crucible.min_steps || !crucible.stop_needs_min_run)
+        {
+            return Some(current);
+        }
+        for (e, (dx, dy)) in DIRECTIONS.into_iter().enumerate() {
+            if d != STILL && e == (d + 2) % 4 && !crucible.can_reverse {
+                continue;
+            }
+            let turn = e != d;
+            if turn && steps < crucible.min_steps {
+                continue;
+            }
+            let next_steps = if turn { 1 } else { steps + 1 };
+            let (nx, ny) = (x + dx, y + dy);
+            if next_steps > crucible.max_steps || !grid.in_range(nx, ny) {
+                continue;
+            }
+            let j = index(nx, ny, e, next_steps);
+            let next_dist = current + (grid.get(nx, ny) - b'0') as usize;
+            if next_dist < dist[j] {
+                dist[j] = next_dist;
+                buckets[next_dist % 10].push(j);
+                queued += 1;
+            }
+        }
+    }
+    None
 }
 
 pub fn main(input: &str) {
     let grid = Grid::<u8>::parse(input);
-    println!("*  {:?}", heat_loss(&grid, 1, 3));
-    println!("** {:?}", heat_loss(&grid, 4, 10));
+    let crucibles = [Crucible::new(&grid, 1, 3), Crucible::new(&grid, 4, 10)];
+    let heat_loss = |crucible| heat_loss_bucketed(&grid, crucible).expect("no route");
+    println!("*  {}", heat_loss(crucibles[0]));
+    println!("** {}", heat_loss(crucibles[1]));
+    for (i, crucible) in crucibles.into_iter().enumerate() {
+        export::write(&format!("route-{}.txt", i + 1), || {
+            let route = best_route(&grid, crucible).expect("no route");
+            format!(
+                "{}\n\nheat loss: {}\n",
+                route.render(&grid),
+                route.heat_loss
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heat_loss(grid: &Grid<u8>, crucible: Crucible) -> usize {
+        best_route(grid, crucible).unwrap().heat_loss
+    }
+
+    const EXAMPLE: &str = "\
+2413432311323
+3215453535623
+3255245654254
+3446585845452
+4546657867536
+1438598798454
+4457876987766
+3637877979653
+4654967986887
+4564679986453
+1224686865563
+2546548887735
+4322674655533";
+
+    const UNFORTUNATE_EXAMPLE: &str = "\
+111111111111
+999999999991
+999999999991
+999999999991
+999999999991";
+
+    /// The heat lost by a crucible crossing `input` with the given run limits.
+    fn crossing(input: &str, min_steps: usize, max_steps: usize) -> usize {
+        let grid = Grid::parse(input);
+        heat_loss(&grid, Crucible::new(&grid, min_steps, max_steps))
+    }
+
+    #[test]
+    fn part_one() {
+        assert_eq!(crossing(EXAMPLE, 1, 3), 102);
+    }
+
+    #[test]
+    fn part_two() {
+        assert_eq!(crossing(EXAMPLE, 4, 10), 94);
+        assert_eq!(crossing(UNFORTUNATE_EXAMPLE, 4, 10), 71);
+    }
+
+    #[test]
+    fn variants() {
+        let grid = Grid::parse(UNFORTUNATE_EXAMPLE);
+        let sloppy = Crucible {
+            stop_needs_min_run: false,
+            ..Crucible::new(&grid, 4, 10)
+        };
+        assert_eq!(heat_loss(&grid, sloppy), 47);
+
+        let grid = Grid::parse(EXAMPLE);
+        let reversing = Crucible {
+            can_reverse: true,
+            ..Crucible::new(&grid, 1, 3)
+        };
+        assert_eq!(heat_loss(&grid, reversing), 101);
+
+        let short_trip = Crucible {
+            start: (2, 0),
+            goal: (0, 0),
+            ..Crucible::new(&grid, 1, 3)
+        };
+        assert_eq!(heat_loss(&grid, short_trip), 6);
+    }
+
+    #[test]
+    fn bucketed() {
+        for input in [EXAMPLE, UNFORTUNATE_EXAMPLE] {
+            let grid = Grid::parse(input);
+            for (min, max) in [(1, 3), (4, 10)] {
+                let crucible = Crucible::new(&grid, min, max);
+                assert_eq!(
+                    heat_loss_bucketed(&grid, crucible).unwrap(),
+                    heat_loss(&grid, crucible)
+                );
+            }
+        }
+        let grid = Grid::parse(EXAMPLE);
+        let reversing = Crucible {
+            can_reverse: true,
+            ..Crucible::new(&grid, 1, 3)
+        };
+        assert_eq!(heat_loss_bucketed(&grid, reversing), Some(101));
+    }
+
+    /// A square city of pseudo-random digits, about the size of a real input.
+    fn random_city(size: usize) -> String {
+        let mut seed: u64 = 17;
+        let mut rows = vec![];
+        for _ in 0..size {
+            let row: String = (0..size)
+                .map(|_| {
+                    seed = seed
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    (b'1' + (seed >> 33) as u8 % 9) as char
+                })
+                .collect();
+            rows.push(row);
+        }
+        rows.join("\n")
+    }
+
+    #[test]
+    fn bucketed_random() {
+        let grid = Grid::parse(&random_city(30));
+        for (min, max) in [(1, 3), (4, 10)] {
+            let crucible = Crucible::new(&grid, min, max);
+            assert_eq!(
+                heat_loss_bucketed(&grid, crucible).unwrap(),
+                heat_loss(&grid, crucible)
+            );
+        }
+    }
+
+    /// Compare the two searches on a full-size city. Run it with
+    /// `cargo test --release benchmark -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn benchmark() {
+        let grid = Grid::parse(&random_city(141));
+        for (min, max) in [(1, 3), (4, 10)] {
+            let crucible = Crucible::new(&grid, min, max);
+            let start = std::time::Instant::now();
+            let bucketed = heat_loss_bucketed(&grid, crucible).unwrap();
+            let bucketed_time = start.elapsed();
+            let start = std::time::Instant::now();
+            let generic = heat_loss(&grid, crucible);
+            let generic_time = start.elapsed();
+            assert_eq!(bucketed, generic);
+            println!("{min}-{max}: bucket queue {bucketed_time:?}, astar {generic_time:?}");
+        }
+    }
+
+    #[test]
+    fn no_route() {
+        let grid = Grid::parse(EXAMPLE);
+        let off_the_map = Crucible {
+            goal: (13, 0),
+            ..Crucible::new(&grid, 1, 3)
+        };
+        assert!(best_route(&grid, off_the_map).is_none());
+        assert_eq!(heat_loss_bucketed(&grid, off_the_map), None);
+        let negative = Crucible {
+            start: (-1, 0),
+            ..Crucible::new(&grid, 1, 3)
+        };
+        assert!(best_route(&grid, negative).is_none());
+        // The ultra crucible can't even get started in a city this small.
+        let grid = Grid::parse("111\n111\n111");
+        let too_close = Crucible::new(&grid, 4, 10);
+        assert!(best_route(&grid, too_close).is_none());
+        assert_eq!(heat_loss_bucketed(&grid, too_close), None);
+    }
+
+    #[test]
+    fn route() {
+        let grid = Grid::parse(EXAMPLE);
+        let route = best_route(&grid, Crucible::new(&grid, 1, 3)).unwrap();
+        let total: usize = route.states[1..]
+            .iter()
+            .map(|s| (grid.get(s.x, s.y) - b'0') as usize)
+            .sum();
+        assert_eq!(total, route.heat_loss);
+    }
+
+    #[test]
+    fn render() {
+        let grid = Grid::parse(UNFORTUNATE_EXAMPLE);
+        let expected = "\
+1>>>>>>>1111
+9999999v9991
+9999999v9991
+9999999v9991
+9999999v>>>>";
+        assert_eq!(
+            best_route(&grid, Crucible::new(&grid, 4, 10))
+                .unwrap()
+                .render(&grid),
+            expected
+        );
+    }
 }
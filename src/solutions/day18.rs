@@ -1,60 +1,20 @@
+use crate::utils::geometry::Polygon;
+
 #[derive(Clone, Copy)]
 enum Mode {
     Regular,
     FromColor,
 }
 
-#[derive(Debug)]
-struct Edge {
-    x1: i64,
-    y1: i64,
-    x2: i64,
-    y2: i64,
-}
-
-#[derive(Debug)]
 struct Lagoon {
-    edges: Vec<Edge>,
+    outline: Polygon,
 }
 
 impl Lagoon {
-    fn inner_area(&self) -> i64 {
-        let mut verticals: Vec<(i64, (i64, i64))> = self
-            .edges
-            .iter()
-            .filter(|e| e.x1 == e.x2)
-            .map(|e| (e.x1, (e.y1.min(e.y2), e.y1.max(e.y2))))
-            .collect();
-        verticals.sort();
-        let mut area = 0;
-        let mut sign = -1;
-
-        let mut ys: Vec<i64> = self.edges.iter().map(|e| e.y1).collect();
-        ys.sort();
-        ys.dedup();
-
-        for w in ys.windows(2) {
-            let height = w[1] - w[0];
-            for (x, (y1, y2)) in &verticals {
-                if *y1 <= w[0] && w[0] < *y2 {
-                    area += x * sign * height;
-                    sign = -sign;
-                }
-            }
-        }
-
-        area
-    }
-
-    fn circumference(&self) -> i64 {
-        self.edges
-            .iter()
-            .map(|e| e.x1.abs_diff(e.x2) + e.y1.abs_diff(e.y2))
-            .sum::<u64>() as i64
-    }
-
+    /// The trench is one cube wide, and dug out along with everything inside
+    /// it, so we count every lattice point on or inside the outline.
     fn area(&self) -> i64 {
-        self.inner_area() + self.circumference() / 2 + 1
+        self.outline.interior_points() + self.outline.boundary_points()
     }
 }
 
@@ -102,21 +62,18 @@ impl Plan {
     }
 
     fn execute(&self, mode: Mode) -> Lagoon {
-        let mut edges = vec![];
+        let mut vertices = vec![];
         let mut x = 0;
         let mut y = 0;
         for step in &self.0 {
+            vertices.push((x, y));
             let (dx, dy) = step.vector(mode);
-            edges.push(Edge {
-                x1: x,
-                y1: y,
-                x2: x + dx,
-                y2: y + dy,
-            });
             x += dx;
             y += dy;
         }
-        Lagoon { edges }
+        Lagoon {
+            outline: Polygon::new(vertices),
+        }
     }
 }
 
@@ -125,3 +82,35 @@ pub fn main(input: &str) {
     println!("*  {:?}", plan.execute(Mode::Regular).area());
     println!("** {:?}", plan.execute(Mode::FromColor).area());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+R 6 (#70c710)
+D 5 (#0dc571)
+L 2 (#5713f0)
+D 2 (#d2c081)
+R 2 (#59c680)
+D 2 (#411b91)
+L 5 (#8ceee2)
+U 2 (#caa173)
+L 1 (#1b58a2)
+U 2 (#caa171)
+R 2 (#7807d2)
+U 3 (#a77fa3)
+L 2 (#015232)
+U 2 (#7a21e3)";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(Plan::parse(EXAMPLE).execute(Mode::Regular).area(), 62);
+    }
+
+    #[test]
+    fn part_two() {
+        let lagoon = Plan::parse(EXAMPLE).execute(Mode::FromColor);
+        assert_eq!(lagoon.area(), 952408144115);
+    }
+}
@@ -1,8 +1,14 @@
 use std::{
     cmp::Ordering,
     collections::{HashMap, HashSet},
+    fmt,
+    process::exit,
 };
 
+use pathfinding::prelude::strongly_connected_components;
+
+use crate::utils::interval::IntervalSet;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Category {
     Xtreme,
@@ -56,6 +62,21 @@ impl Condition {
             _ => self.value + 1,
         }
     }
+
+    /// Split a region into the parts that match this condition and the parts
+    /// that don't.
+    fn split(&self, region: &Region) -> (Region, Region) {
+        let i = self.category as usize;
+        let (below, above) = region.0[i].split_at(self.threshold());
+        let mut lower = region.clone();
+        let mut upper = region.clone();
+        lower.0[i] = below;
+        upper.0[i] = above;
+        match self.ordering {
+            Ordering::Less => (lower, upper),
+            _ => (upper, lower),
+        }
+    }
 }
 
 // a<2006:qkq
@@ -82,7 +103,7 @@ impl Rule {
 
     fn destination(&self) -> &str {
         match self {
-            Rule::If(_, s) | Rule::Goto(s) => &s,
+            Rule::If(_, s) | Rule::Goto(s) => s,
         }
     }
 }
@@ -108,17 +129,55 @@ impl Workflow {
         }
         panic!("no rule matched");
     }
+}
 
-    fn thresholds(&self, category: Category) -> HashSet<i64> {
-        let mut set = HashSet::new();
-        for rule in &self.rules {
-            if let Rule::If(c, _) = rule {
-                if c.category == category {
-                    set.insert(c.threshold());
-                }
+/// Something odd about a program, found without running any parts through it.
+#[derive(Debug, PartialEq, Eq)]
+enum Finding {
+    Unreachable(String),
+    /// Workflows that can send parts around in a loop.
+    Cycle(Vec<String>),
+    UndefinedTarget {
+        workflow: String,
+        target: String,
+    },
+    /// A rule (numbered from 0) that no part can reach, because the rules
+    /// before it catch everything it would match.
+    ShadowedRule {
+        workflow: String,
+        rule: usize,
+    },
+    /// A workflow that sends every part to the same place.
+    SameDestination {
+        workflow: String,
+        destination: String,
+    },
+}
+
+impl Finding {
+    /// Whether running parts through the program could loop forever or reach
+    /// a workflow that doesn't exist.
+    fn is_fatal(&self) -> bool {
+        matches!(self, Finding::Cycle(_) | Finding::UndefinedTarget { .. })
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Finding::Unreachable(w) => write!(f, "{w} can't be reached from in"),
+            Finding::Cycle(ws) => write!(f, "{} form a cycle", ws.join(", ")),
+            Finding::UndefinedTarget { workflow, target } => {
+                write!(f, "{workflow} sends parts to undefined workflow {target}")
             }
+            Finding::ShadowedRule { workflow, rule } => {
+                write!(f, "rule {rule} of {workflow} never matches anything")
+            }
+            Finding::SameDestination {
+                workflow,
+                destination,
+            } => write!(f, "{workflow} always sends parts to {destination}"),
         }
-        set
     }
 }
 
@@ -150,12 +209,156 @@ impl Program {
         state.as_bytes()[0] == b'A'
     }
 
-    fn thresholds(&self, category: Category) -> HashSet<i64> {
-        let mut set = HashSet::new();
-        for workflow in self.workflows.values() {
-            set.extend(&workflow.thresholds(category))
+    fn accepted_rating_sum(&self, parts: &[Part]) -> i64 {
+        parts
+            .iter()
+            .filter(|p| self.accepts(p))
+            .map(|p| p.total_rating())
+            .sum()
+    }
+
+    /// Push every possible part through the workflows at once, splitting the
+    /// space of ratings at each condition, and collect the regions that end up
+    /// accepted. They're disjoint, since each split sends a part one way only.
+    fn accepted_regions(&self) -> Vec<Region> {
+        let mut accepted = vec![];
+        let mut queue = vec![("in", Region::full())];
+        while let Some((name, mut region)) = queue.pop() {
+            let workflow = self.workflows.get(name).expect("unknown workflow");
+            for rule in &workflow.rules {
+                let (matched, rest) = match rule {
+                    Rule::If(c, _) => c.split(&region),
+                    Rule::Goto(_) => (region, Region::empty()),
+                };
+                if !matched.is_empty() {
+                    match rule.destination() {
+                        "A" => accepted.push(matched),
+                        "R" => {}
+                        next => queue.push((next, matched)),
+                    }
+                }
+                region = rest;
+                if region.is_empty() {
+                    break;
+                }
+            }
+        }
+        accepted
+    }
+
+    fn accepted_combinations(&self) -> i64 {
+        self.accepted_regions().iter().map(Region::volume).sum()
+    }
+
+    /// Look for dead code and other oddities in the workflows.
+    fn analyze(&self) -> Vec<Finding> {
+        let mut names: Vec<&str> = self.workflows.keys().map(|k| k.as_str()).collect();
+        names.sort();
+        let successors = |name: &str| -> Vec<&str> {
+            let mut next: Vec<&str> = self.workflows[name]
+                .rules
+                .iter()
+                .map(Rule::destination)
+                .filter(|d| self.workflows.contains_key(*d))
+                .collect();
+            next.dedup();
+            next
+        };
+        let mut findings = vec![];
+
+        let mut reached = HashSet::from(["in"]);
+        let mut queue = vec!["in"];
+        while let Some(name) = queue.pop() {
+            if self.workflows.contains_key(name) {
+                for next in successors(name) {
+                    if reached.insert(next) {
+                        queue.push(next);
+                    }
+                }
+            }
+        }
+        for name in &names {
+            if !reached.contains(name) {
+                findings.push(Finding::Unreachable(name.to_string()));
+            }
+        }
+
+        let mut cycles = strongly_connected_components(&names, |&n| successors(n));
+        cycles.retain(|c| c.len() > 1 || successors(c[0]).contains(&c[0]));
+        for mut cycle in cycles {
+            cycle.sort();
+            findings.push(Finding::Cycle(
+                cycle.iter().map(|w| w.to_string()).collect(),
+            ));
+        }
+
+        for name in &names {
+            let rules = &self.workflows[*name].rules;
+            for rule in rules {
+                let target = rule.destination();
+                if target != "A" && target != "R" && !self.workflows.contains_key(target) {
+                    findings.push(Finding::UndefinedTarget {
+                        workflow: name.to_string(),
+                        target: target.to_owned(),
+                    });
+                }
+            }
+
+            // Follow the parts that reach each rule, like accepted_regions.
+            let mut region = Region::full();
+            for (i, rule) in rules.iter().enumerate() {
+                let (matched, rest) = match rule {
+                    Rule::If(c, _) => c.split(&region),
+                    Rule::Goto(_) => (region, Region::empty()),
+                };
+                if matched.is_empty() {
+                    findings.push(Finding::ShadowedRule {
+                        workflow: name.to_string(),
+                        rule: i,
+                    });
+                }
+                region = rest;
+            }
+
+            let first = rules[0].destination();
+            if rules.iter().all(|r| r.destination() == first) {
+                findings.push(Finding::SameDestination {
+                    workflow: name.to_string(),
+                    destination: first.to_owned(),
+                });
+            }
         }
-        set
+        findings
+    }
+}
+
+/// A set of parts, given by which ratings are allowed in each category.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Region([IntervalSet; 4]);
+
+impl Region {
+    fn full() -> Self {
+        Self(std::array::from_fn(|_| IntervalSet::from(1..4001)))
+    }
+
+    fn empty() -> Self {
+        Self(Default::default())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().any(IntervalSet::is_empty)
+    }
+
+    fn volume(&self) -> i64 {
+        self.0.iter().map(IntervalSet::total_length).product()
+    }
+
+    #[allow(dead_code)]
+    fn contains(&self, part: &Part) -> bool {
+        self.0
+            .iter()
+            .zip(part.0)
+            .all(|(set, rating)| set.contains(rating))
     }
 }
 
@@ -186,40 +389,111 @@ impl Part {
 pub fn main(input: &str) {
     let (program, parts) = input.split_once("\n\n").unwrap();
     let program = Program::parse(program);
+    let findings = program.analyze();
+    for finding in &findings {
+        let severity = if finding.is_fatal() {
+            "error"
+        } else {
+            "warning"
+        };
+        eprintln!("{severity}: {finding}");
+    }
+    if findings.iter().any(Finding::is_fatal) {
+        exit(1);
+    }
     let parts: Vec<Part> = parts.split('\n').map(Part::parse).collect();
 
-    let accepted_rating_sum: i64 = parts
-        .iter()
-        .filter(|p| program.accepts(p))
-        .map(|p| p.total_rating())
-        .sum();
-
-    println!("*  {accepted_rating_sum}");
-
-    let thresholds = |category: Category| -> Vec<(i64, i64)> {
-        let mut t: Vec<i64> = program.thresholds(category).into_iter().collect();
-        t.push(1);
-        t.sort();
-        t.iter()
-            .zip(t[1..].iter().chain(&[4001]))
-            .map(|(x, y)| (*x, *y))
-            .collect()
-    };
-
-    // This little maneuver is gonna cost us 20 minutes. Merry Christmas!!!
-    let mut total = 0;
-    for (x0, x1) in thresholds(Category::Xtreme) {
-        for (m0, m1) in thresholds(Category::Musical) {
-            for (a0, a1) in thresholds(Category::Aerodynamic) {
-                for (s0, s1) in thresholds(Category::Shiny) {
-                    let count = (x1 - x0) * (m1 - m0) * (a1 - a0) * (s1 - s0);
-                    if program.accepts(&Part([x0, m0, a0, s0])) {
-                        total += count;
-                    }
-                }
-            }
-        }
+    println!("*  {}", program.accepted_rating_sum(&parts));
+    println!("** {}", program.accepted_combinations());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+px{a<2006:qkq,m>2090:A,rfg}
+pv{a>1716:R,A}
+lnx{m>1548:A,A}
+rfg{s<537:gd,x>2440:R,A}
+qs{s>3448:A,lnx}
+qkq{x<1416:A,crn}
+crn{x>2662:A,R}
+in{s<1351:px,qqz}
+qqz{s>2770:qs,m<1801:hdj,R}
+gd{a>3333:R,R}
+hdj{m>838:A,pv}
+
+{x=787,m=2655,a=1222,s=2876}
+{x=1679,m=44,a=2067,s=496}
+{x=2036,m=264,a=79,s=2244}
+{x=2461,m=1339,a=466,s=291}
+{x=2127,m=1623,a=2188,s=1013}";
+
+    #[test]
+    fn part_one() {
+        let (program, parts) = EXAMPLE.split_once("\n\n").unwrap();
+        let program = Program::parse(program);
+        let parts: Vec<Part> = parts.split('\n').map(Part::parse).collect();
+        assert_eq!(program.accepted_rating_sum(&parts), 19114);
     }
 
-    println!("** {total}");
+    #[test]
+    fn part_two() {
+        let (program, _) = EXAMPLE.split_once("\n\n").unwrap();
+        let program = Program::parse(program);
+        assert_eq!(program.accepted_combinations(), 167409079868000);
+    }
+
+    #[test]
+    fn analyze() {
+        let (program, _) = EXAMPLE.split_once("\n\n").unwrap();
+        let findings: Vec<String> = Program::parse(program)
+            .analyze()
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+        assert_eq!(
+            findings,
+            ["gd always sends parts to R", "lnx always sends parts to A"]
+        );
+
+        let program = Program::parse(
+            "\
+in{x<100:a,x<50:R,b}
+a{m>10:b,A}
+b{s<5:a,c}
+d{A}",
+        );
+        let expected = vec![
+            Finding::Unreachable("d".to_owned()),
+            Finding::Cycle(vec!["a".to_owned(), "b".to_owned()]),
+            Finding::UndefinedTarget {
+                workflow: "b".to_owned(),
+                target: "c".to_owned(),
+            },
+            Finding::SameDestination {
+                workflow: "d".to_owned(),
+                destination: "A".to_owned(),
+            },
+            Finding::ShadowedRule {
+                workflow: "in".to_owned(),
+                rule: 1,
+            },
+        ];
+        assert_eq!(program.analyze(), expected);
+        let fatal: Vec<&Finding> = expected.iter().filter(|f| f.is_fatal()).collect();
+        assert_eq!(fatal, [&expected[1], &expected[2]]);
+    }
+
+    #[test]
+    fn accepted_regions() {
+        let (program, parts) = EXAMPLE.split_once("\n\n").unwrap();
+        let program = Program::parse(program);
+        let regions = program.accepted_regions();
+        for part in parts.split('\n').map(Part::parse) {
+            let inside = regions.iter().filter(|r| r.contains(&part)).count();
+            assert_eq!(inside, program.accepts(&part) as usize);
+        }
+    }
 }
@@ -1,5 +1,12 @@
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::collections::VecDeque;
+use std::fmt::{self, Write};
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+use crate::utils::{export, math::crt};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum Pulse {
@@ -50,11 +57,130 @@ impl Module {
     fn process_pulse(&mut self, input: &str, pulse: Pulse) -> Option<Pulse> {
         self.behavior.process_pulse(input, pulse)
     }
+
+    /// The module's name with its type prefix, like in the input.
+    fn label(&self, name: &str) -> String {
+        match self.behavior {
+            Behavior::FlipFlop { .. } => format!("%{name}"),
+            Behavior::Conjunction { .. } => format!("&{name}"),
+            Behavior::Broadcast => name.to_owned(),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 struct Circuit {
     modules: HashMap<String, Module>,
+    /// How many times the button has been pushed.
+    presses: usize,
+    /// If set, every pulse gets recorded here.
+    trace: Option<Trace>,
+}
+
+/// Something suspicious about a circuit's definition.
+#[derive(Debug, PartialEq, Eq)]
+enum Problem {
+    UndefinedDestination { module: String, destination: String },
+    DuplicateDefinition(String),
+    Unreachable(String),
+    ConjunctionWithoutInputs(String),
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::UndefinedDestination {
+                module,
+                destination,
+            } => write!(f, "{module} sends pulses to undefined module {destination}"),
+            Problem::DuplicateDefinition(name) => write!(f, "{name} is defined more than once"),
+            Problem::Unreachable(name) => write!(f, "{name} can't be reached from broadcaster"),
+            Problem::ConjunctionWithoutInputs(name) => {
+                write!(f, "conjunction {name} has no inputs")
+            }
+        }
+    }
+}
+
+/// A snapshot of everything in a circuit that can change.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct State(Vec<u64>);
+
+/// After `start` presses, the circuit goes through the same states every
+/// `period` presses, sending this many pulses each time around.
+#[derive(Debug, PartialEq, Eq)]
+struct Cycle {
+    start: usize,
+    period: usize,
+    low_pulses: usize,
+    high_pulses: usize,
+}
+
+/// A pulse recorded by a `Trace`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct TracedPulse {
+    press: usize,
+    sender: String,
+    level: Pulse,
+    receiver: String,
+    /// How many pulses were still waiting to be delivered after this one.
+    queue_depth: usize,
+}
+
+impl fmt::Display for TracedPulse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let level = match self.level {
+            Pulse::Low => "low",
+            Pulse::High => "high",
+        };
+        write!(
+            f,
+            "{} {} -{}-> {} ({} queued)",
+            self.press, self.sender, level, self.receiver, self.queue_depth
+        )
+    }
+}
+
+/// A record of the pulses sent while pushing the button, optionally limited to
+/// pulses to or from some modules.
+#[derive(Clone, Debug, Default)]
+struct Trace {
+    modules: Option<HashSet<String>>,
+    pulses: Vec<TracedPulse>,
+}
+
+impl Trace {
+    /// A trace that only records pulses sent or received by these modules.
+    fn only(modules: &[&str]) -> Self {
+        Self {
+            modules: Some(modules.iter().map(|m| m.to_string()).collect()),
+            pulses: vec![],
+        }
+    }
+
+    fn record(&mut self, pulse: TracedPulse) {
+        let wanted = self
+            .modules
+            .as_ref()
+            .is_none_or(|m| m.contains(&pulse.sender) || m.contains(&pulse.receiver));
+        if wanted {
+            self.pulses.push(pulse);
+        }
+    }
+
+    /// Write the trace one pulse per line.
+    fn write(&self, out: &mut impl io::Write) -> io::Result<()> {
+        for pulse in &self.pulses {
+            writeln!(out, "{pulse}")?;
+        }
+        Ok(())
+    }
+
+    fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write(&mut out)?;
+        io::Write::flush(&mut out)
+    }
 }
 
 fn parse_name(name: &str) -> (Behavior, String) {
@@ -70,10 +196,17 @@ fn parse_name(name: &str) -> (Behavior, String) {
     }
 }
 
+/// How many times `presses_until_rx` pushes the button while timing the
+/// inputs before it gives up. Real inputs need fewer than ten thousand.
+const PRESS_LIMIT: usize = 100_000;
+
+#[derive(Debug, PartialEq, Eq)]
 struct Report {
     low_pulses: usize,
     low_pulses_to_rx: usize,
     high_pulses: usize,
+    /// Modules that sent a high pulse to the watched module, if any.
+    high_senders_to_watched: Vec<String>,
 }
 
 impl Circuit {
@@ -100,15 +233,169 @@ impl Circuit {
                 }
             }
         }
-        Self { modules }
+        Self {
+            modules,
+            presses: 0,
+            trace: None,
+        }
+    }
+
+    /// Look for problems in a circuit definition that `parse` would quietly
+    /// accept. Sending pulses to an undefined `rx` is fine.
+    fn check(input: &str) -> Vec<Problem> {
+        let mut problems = vec![];
+        let mut defined = HashSet::new();
+        for line in input.split('\n') {
+            let (before, _) = line.split_once(" -> ").unwrap();
+            let (_, name) = parse_name(before);
+            if !defined.insert(name.clone()) {
+                problems.push(Problem::DuplicateDefinition(name));
+            }
+        }
+
+        let circuit = Self::parse(input);
+        let (names, _) = circuit.node_names();
+        for name in &names {
+            let module = &circuit.modules[*name];
+            for d in &module.destinations {
+                if d != "rx" && !circuit.modules.contains_key(d) {
+                    problems.push(Problem::UndefinedDestination {
+                        module: name.to_string(),
+                        destination: d.clone(),
+                    });
+                }
+            }
+            if let Behavior::Conjunction { inputs } = &module.behavior {
+                if inputs.is_empty() {
+                    problems.push(Problem::ConjunctionWithoutInputs(name.to_string()));
+                }
+            }
+        }
+
+        let mut reached = HashSet::from(["broadcaster"]);
+        let mut queue = VecDeque::from(["broadcaster"]);
+        while let Some(name) = queue.pop_front() {
+            let Some(module) = circuit.modules.get(name) else {
+                continue;
+            };
+            for d in &module.destinations {
+                if reached.insert(d) {
+                    queue.push_back(d);
+                }
+            }
+        }
+        for name in &names {
+            if !reached.contains(name) {
+                problems.push(Problem::Unreachable(name.to_string()));
+            }
+        }
+        problems
+    }
+
+    fn inputs_of(&self, name: &str) -> Vec<String> {
+        self.modules
+            .iter()
+            .filter(|(_, m)| m.destinations.iter().any(|d| d == name))
+            .map(|(k, _)| k.clone())
+            .collect()
+    }
+
+    /// The module names, and the destinations that aren't modules (like
+    /// `rx`), each sorted so that exports come out the same every time.
+    fn node_names(&self) -> (Vec<&str>, Vec<&str>) {
+        let mut names: Vec<&str> = self.modules.keys().map(|k| k.as_str()).collect();
+        names.sort();
+        let mut sinks: Vec<&str> = self
+            .modules
+            .values()
+            .flat_map(|m| m.destinations.iter().map(|d| d.as_str()))
+            .filter(|d| !self.modules.contains_key(*d))
+            .collect();
+        sinks.sort();
+        sinks.dedup();
+        (names, sinks)
+    }
+
+    /// Draw the circuit as a Graphviz digraph: flip-flops are boxes,
+    /// conjunctions are diamonds, and `rx` is a red double circle.
+    fn dot(&self) -> String {
+        let (names, sinks) = self.node_names();
+        let mut out = String::from("digraph circuit {\n");
+        for name in &names {
+            let module = &self.modules[*name];
+            let shape = match module.behavior {
+                Behavior::FlipFlop { .. } => "box",
+                Behavior::Conjunction { .. } => "diamond",
+                Behavior::Broadcast => "doubleoctagon",
+            };
+            let label = export::dot_id(&module.label(name));
+            let name = export::dot_id(name);
+            writeln!(out, "    {name} [shape={shape}, label={label}];").unwrap();
+        }
+        for sink in &sinks {
+            let id = export::dot_id(sink);
+            if *sink == "rx" {
+                writeln!(
+                    out,
+                    "    {id} [shape=doublecircle, style=filled, fillcolor=red];"
+                )
+                .unwrap();
+            } else {
+                writeln!(out, "    {id} [shape=circle];").unwrap();
+            }
+        }
+        for name in &names {
+            for dst in &self.modules[*name].destinations {
+                let (name, dst) = (export::dot_id(name), export::dot_id(dst));
+                writeln!(out, "    {name} -> {dst};").unwrap();
+            }
+        }
+        out.push_str("}\n");
+        out
     }
 
-    fn push_button(&mut self) -> Report {
+    /// Draw the circuit as a Mermaid flowchart, with shapes like `dot`'s.
+    fn mermaid(&self) -> String {
+        let (names, sinks) = self.node_names();
+        let mut out = String::from("flowchart LR\n");
+        for name in &names {
+            let module = &self.modules[*name];
+            let id = export::mermaid_id(name);
+            let label = export::mermaid_label(&module.label(name));
+            match module.behavior {
+                Behavior::FlipFlop { .. } => writeln!(out, "    {id}[{label}]"),
+                Behavior::Conjunction { .. } => writeln!(out, "    {id}{{{label}}}"),
+                Behavior::Broadcast => writeln!(out, "    {id}{{{{{label}}}}}"),
+            }
+            .unwrap();
+        }
+        for sink in &sinks {
+            let id = export::mermaid_id(sink);
+            let label = export::mermaid_label(sink);
+            if *sink == "rx" {
+                writeln!(out, "    {id}((({label})))").unwrap();
+                writeln!(out, "    style {id} fill:#f66").unwrap();
+            } else {
+                writeln!(out, "    {id}(({label}))").unwrap();
+            }
+        }
+        for name in &names {
+            for dst in &self.modules[*name].destinations {
+                let (src, dst) = (export::mermaid_id(name), export::mermaid_id(dst));
+                writeln!(out, "    {src} --> {dst}").unwrap();
+            }
+        }
+        out
+    }
+
+    fn push_button(&mut self, watched: Option<&str>) -> Report {
+        self.presses += 1;
         let mut pulse_queue =
             VecDeque::from([("button".to_owned(), Pulse::Low, "broadcaster".to_owned())]);
         let mut low_pulses = 0;
         let mut low_pulses_to_rx = 0;
         let mut high_pulses = 0;
+        let mut high_senders_to_watched = vec![];
         while let Some((s0, pulse, s1)) = pulse_queue.pop_front() {
             match pulse {
                 Pulse::Low => {
@@ -117,9 +404,22 @@ impl Circuit {
                         low_pulses_to_rx += 1;
                     }
                 }
-                Pulse::High => high_pulses += 1,
+                Pulse::High => {
+                    high_pulses += 1;
+                    if watched == Some(&s1) {
+                        high_senders_to_watched.push(s0.clone());
+                    }
+                }
+            }
+            if let Some(trace) = &mut self.trace {
+                trace.record(TracedPulse {
+                    press: self.presses,
+                    sender: s0.clone(),
+                    level: pulse,
+                    receiver: s1.clone(),
+                    queue_depth: pulse_queue.len(),
+                });
             }
-            // println!("{s0} {pulse:?} {s1}");
             if let Some(m1) = self.modules.get_mut(&s1) {
                 let response = m1.process_pulse(&s0, pulse);
                 if let Some(pulse) = response {
@@ -133,41 +433,637 @@ impl Circuit {
             low_pulses,
             low_pulses_to_rx,
             high_pulses,
+            high_senders_to_watched,
         }
     }
 
-    fn simulate(&mut self, steps: usize) -> usize {
+    /// Every flip-flop's bit and every conjunction's memory of its inputs,
+    /// packed into bits in order of module name (and input name).
+    fn state(&self) -> State {
+        let mut names: Vec<&String> = self.modules.keys().collect();
+        names.sort();
+        let mut bits = vec![];
+        for name in names {
+            match &self.modules[name].behavior {
+                Behavior::FlipFlop { on } => bits.push(*on),
+                Behavior::Conjunction { inputs } => {
+                    let mut inputs: Vec<(&String, &Pulse)> = inputs.iter().collect();
+                    inputs.sort_by_key(|(name, _)| *name);
+                    bits.extend(inputs.iter().map(|(_, &p)| p == Pulse::High));
+                }
+                Behavior::Broadcast => {}
+            }
+        }
+        State(
+            bits.chunks(64)
+                .map(|c| c.iter().rev().fold(0, |acc, &b| acc << 1 | b as u64))
+                .collect(),
+        )
+    }
+
+    /// Push the button `steps` times, returning the total low and high pulses.
+    /// If the circuit gets back to a state it's been in before, skip ahead
+    /// by whole cycles, and report the cycle.
+    fn run(&mut self, steps: usize) -> (usize, usize, Option<Cycle>) {
+        let mut seen: HashMap<State, (usize, usize, usize)> = HashMap::new();
+        seen.insert(self.state(), (0, 0, 0));
         let mut low_pulses = 0;
         let mut high_pulses = 0;
-        for _ in 0..steps {
-            let report = self.push_button();
+        let mut cycle = None;
+        let mut n = 0;
+        while n < steps {
+            let report = self.push_button(None);
+            n += 1;
             low_pulses += report.low_pulses;
             high_pulses += report.high_pulses;
+            if cycle.is_some() {
+                continue;
+            }
+            if let Some((start, low, high)) =
+                seen.insert(self.state(), (n, low_pulses, high_pulses))
+            {
+                let found = Cycle {
+                    start,
+                    period: n - start,
+                    low_pulses: low_pulses - low,
+                    high_pulses: high_pulses - high,
+                };
+                // Skipped presses wouldn't make it into the trace, so keep
+                // pushing the button if there is one.
+                let skipped = if self.trace.is_some() {
+                    0
+                } else {
+                    (steps - n) / found.period
+                };
+                low_pulses += skipped * found.low_pulses;
+                high_pulses += skipped * found.high_pulses;
+                n += skipped * found.period;
+                self.presses += skipped * found.period;
+                cycle = Some(found);
+            }
         }
+        (low_pulses, high_pulses, cycle)
+    }
+
+    fn simulate(&mut self, steps: usize) -> usize {
+        let (low_pulses, high_pulses, _) = self.run(steps);
         low_pulses * high_pulses
     }
 
-    fn presses_until_rx(&mut self) -> usize {
-        for n in 1.. {
-            let report = self.push_button();
+    /// `rx` is fed by a single conjunction, which only sends it a low pulse
+    /// when its inputs have all most recently sent it high pulses. Each input
+    /// is driven by its own counter-like sub-circuit that goes high on a fixed
+    /// schedule, so we time each one separately and combine the schedules.
+    fn presses_until_rx(&self) -> usize {
+        let feeders = self.inputs_of("rx");
+        let [feeder] = &feeders[..] else {
+            panic!("expected exactly one module feeding rx, got {feeders:?}");
+        };
+        let Behavior::Conjunction { inputs } = &self.modules[feeder].behavior else {
+            panic!("expected {feeder} to be a conjunction");
+        };
+
+        // Record the first two presses on which each input goes high.
+        let mut hits: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut circuit = CompiledCircuit::compile(self);
+        for n in 1..=PRESS_LIMIT {
+            let report = circuit.push_button(Some(feeder));
             if report.low_pulses_to_rx > 0 {
                 return n;
             }
-            for (k, v) in &self.modules {
-                if let Behavior::FlipFlop { on } = v.behavior {
-                    print!("{k}{} ", on as usize);
+            for sender in report.high_senders_to_watched {
+                let presses = hits.entry(sender).or_default();
+                if presses.last() != Some(&n) {
+                    presses.push(n);
                 }
             }
-            println!()
+            if inputs
+                .keys()
+                .all(|k| hits.get(k).is_some_and(|h| h.len() >= 2))
+            {
+                break;
+            }
+        }
+        let mut missing: Vec<&str> = inputs
+            .keys()
+            .filter(|k| hits.get(*k).is_none_or(|h| h.len() < 2))
+            .map(|k| k.as_str())
+            .collect();
+        if !missing.is_empty() {
+            missing.sort();
+            panic!(
+                "gave up after {PRESS_LIMIT} presses: {} never went high twice",
+                missing.join(", ")
+            );
         }
-        unreachable!()
+
+        // Each input goes high on presses first + k * period, so we want the
+        // first press that is on every schedule at once.
+        let schedules: Vec<(i128, i128)> = hits
+            .values()
+            .map(|h| (h[0] as i128, (h[1] - h[0]) as i128))
+            .collect();
+        let congruences: Vec<(i128, i128)> = schedules.iter().map(|&(f, p)| (f % p, p)).collect();
+        let (x, period) = crt(&congruences).expect("the inputs never line up");
+        let earliest = schedules.iter().map(|&(f, _)| f).max().unwrap();
+        let x = if x < earliest {
+            x + (earliest - x + period - 1) / period * period
+        } else {
+            x
+        };
+        x as usize
+    }
+}
+
+/// What a module in a `CompiledCircuit` does with the pulses it receives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Gate {
+    FlipFlop,
+    Conjunction,
+    Broadcast,
+    /// A destination that isn't a module, like `rx` (or the button).
+    Sink,
+}
+
+/// A `Circuit` with its modules numbered, so that pushing the button doesn't
+/// touch any strings or hash maps. Conjunctions remember their inputs as a
+/// bitmask, and pulses wait in a ring buffer that's reused between presses.
+/// Part two pushes the button thousands of times, so it uses this; part one
+/// sticks with `Circuit::simulate`, which can trace and skip cycles.
+#[derive(Clone, Debug)]
+struct CompiledCircuit {
+    names: Vec<String>,
+    gates: Vec<Gate>,
+    /// Each module's destinations, along with which bit of the destination's
+    /// memory the module is (if the destination is a conjunction).
+    destinations: Vec<Vec<(usize, u32)>>,
+    /// Flip-flops' states, and conjunctions' memories of high inputs.
+    memory: Vec<u64>,
+    /// The memory a conjunction has when all its inputs were high.
+    all_high: Vec<u64>,
+    button: usize,
+    broadcaster: usize,
+    rx: Option<usize>,
+    /// Pulses as (sender, pulse, receiver, the sender's bit in the receiver).
+    queue: Vec<(usize, Pulse, usize, u32)>,
+}
+
+impl CompiledCircuit {
+    fn compile(circuit: &Circuit) -> Self {
+        let (modules, sinks) = circuit.node_names();
+        let mut names: Vec<String> = modules
+            .iter()
+            .chain(&sinks)
+            .map(|n| n.to_string())
+            .collect();
+        names.push("button".to_owned());
+        let index: HashMap<&str, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.as_str(), i))
+            .collect();
+
+        let mut gates = vec![Gate::Sink; names.len()];
+        let mut memory = vec![0; names.len()];
+        let mut all_high = vec![0; names.len()];
+        let mut destinations = vec![vec![]; names.len()];
+        // Conjunctions number their inputs in the order they're found.
+        let mut inputs = vec![0; names.len()];
+        for (i, name) in modules.iter().enumerate() {
+            let module = &circuit.modules[*name];
+            gates[i] = match &module.behavior {
+                Behavior::FlipFlop { on } => {
+                    memory[i] = *on as u64;
+                    Gate::FlipFlop
+                }
+                Behavior::Conjunction { .. } => Gate::Conjunction,
+                Behavior::Broadcast => Gate::Broadcast,
+            };
+        }
+        for (i, name) in modules.iter().enumerate() {
+            for d in &circuit.modules[*name].destinations {
+                let j = index[d.as_str()];
+                let mut bit = 0;
+                if let Some(Module {
+                    behavior: Behavior::Conjunction { inputs: remembered },
+                    ..
+                }) = circuit.modules.get(d)
+                {
+                    // Listing a conjunction twice still makes one input.
+                    bit = match destinations[i].iter().find(|&&(k, _)| k == j) {
+                        Some(&(_, bit)) => bit,
+                        None => {
+                            inputs[j] += 1;
+                            inputs[j] - 1
+                        }
+                    };
+                    assert!(bit < 64, "{d} has more than 64 inputs");
+                    all_high[j] |= 1 << bit;
+                    if remembered[*name] == Pulse::High {
+                        memory[j] |= 1 << bit;
+                    }
+                }
+                destinations[i].push((j, bit));
+            }
+        }
+
+        Self {
+            gates,
+            destinations,
+            memory,
+            all_high,
+            button: index["button"],
+            broadcaster: *index.get("broadcaster").expect("no broadcaster"),
+            rx: index.get("rx").copied(),
+            queue: vec![(0, Pulse::Low, 0, 0); 64],
+            names,
+        }
+    }
+
+    /// Push the button once, like `Circuit::push_button`.
+    fn push_button(&mut self, watched: Option<&str>) -> Report {
+        let watched = watched.and_then(|w| self.names.iter().position(|n| n == w));
+        let mut low_pulses = 0;
+        let mut low_pulses_to_rx = 0;
+        let mut high_pulses = 0;
+        let mut high_senders_to_watched = vec![];
+
+        // The queue holds pulses from `head` up to (but not including)
+        // `tail`, modulo its length, which is always a power of two.
+        self.queue[0] = (self.button, Pulse::Low, self.broadcaster, 0);
+        let (mut head, mut tail) = (0, 1);
+        while head != tail {
+            let mask = self.queue.len() - 1;
+            let (sender, pulse, receiver, bit) = self.queue[head & mask];
+            head += 1;
+            match pulse {
+                Pulse::Low => {
+                    low_pulses += 1;
+                    if Some(receiver) == self.rx {
+                        low_pulses_to_rx += 1;
+                    }
+                }
+                Pulse::High => {
+                    high_pulses += 1;
+                    if Some(receiver) == watched {
+                        high_senders_to_watched.push(self.names[sender].clone());
+                    }
+                }
+            }
+
+            let response = match self.gates[receiver] {
+                Gate::FlipFlop if pulse == Pulse::Low => {
+                    self.memory[receiver] ^= 1;
+                    if self.memory[receiver] == 1 {
+                        Pulse::High
+                    } else {
+                        Pulse::Low
+                    }
+                }
+                Gate::Conjunction => {
+                    match pulse {
+                        Pulse::High => self.memory[receiver] |= 1 << bit,
+                        Pulse::Low => self.memory[receiver] &= !(1 << bit),
+                    }
+                    if self.memory[receiver] == self.all_high[receiver] {
+                        Pulse::Low
+                    } else {
+                        Pulse::High
+                    }
+                }
+                Gate::Broadcast => pulse,
+                _ => continue,
+            };
+
+            for k in 0..self.destinations[receiver].len() {
+                if tail - head == self.queue.len() {
+                    self.grow_queue(head, tail);
+                    (head, tail) = (0, tail - head);
+                }
+                let mask = self.queue.len() - 1;
+                let (d, bit) = self.destinations[receiver][k];
+                self.queue[tail & mask] = (receiver, response, d, bit);
+                tail += 1;
+            }
+        }
+        Report {
+            low_pulses,
+            low_pulses_to_rx,
+            high_pulses,
+            high_senders_to_watched,
+        }
+    }
+
+    /// Double the queue's size, moving the waiting pulses to the front.
+    fn grow_queue(&mut self, head: usize, tail: usize) {
+        let mask = self.queue.len() - 1;
+        let mut queue = vec![(0, Pulse::Low, 0, 0); self.queue.len() * 2];
+        for (i, slot) in (head..tail).zip(&mut queue) {
+            *slot = self.queue[i & mask];
+        }
+        self.queue = queue;
     }
 }
 
 pub fn main(input: &str) {
-    let mut circuit = Circuit::parse(input);
-    println!("*  {}", circuit.simulate(1000));
+    for problem in Circuit::check(input) {
+        eprintln!("warning: {problem}");
+    }
+    let circuit = Circuit::parse(input);
+    export::write("circuit.dot", || circuit.dot());
+    export::write("circuit.mmd", || circuit.mermaid());
 
-    // Ah, yes, and this takes forever. Sadly I have a job, ERIC
+    // Set AOC_TRACE_MODULES to a list like `rx,kc` to only trace some modules.
+    let mut pressed = circuit.clone();
+    let trace_path = export::path("trace.txt");
+    if trace_path.is_some() {
+        pressed.trace = Some(match std::env::var("AOC_TRACE_MODULES") {
+            Ok(modules) => Trace::only(&modules.split(',').collect::<Vec<_>>()),
+            Err(_) => Trace::default(),
+        });
+    }
+    println!("*  {}", pressed.simulate(1000));
+    if let (Some(path), Some(trace)) = (trace_path, pressed.trace) {
+        trace.save(&path).expect("couldn't write trace");
+        eprintln!("wrote {}", path.display());
+    }
     println!("** {}", circuit.presses_until_rx());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+broadcaster -> a, b, c
+%a -> b
+%b -> c
+%c -> inv
+&inv -> a";
+
+    const INTERESTING_EXAMPLE: &str = "\
+broadcaster -> a
+%a -> inv, con
+&inv -> b
+%b -> con
+&con -> output";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(Circuit::parse(EXAMPLE).simulate(1000), 32000000);
+        assert_eq!(Circuit::parse(INTERESTING_EXAMPLE).simulate(1000), 11687500);
+    }
+
+    #[test]
+    fn dot() {
+        let expected = "\
+digraph circuit {
+    \"a\" [shape=box, label=\"%a\"];
+    \"b\" [shape=box, label=\"%b\"];
+    \"broadcaster\" [shape=doubleoctagon, label=\"broadcaster\"];
+    \"con\" [shape=diamond, label=\"&con\"];
+    \"inv\" [shape=diamond, label=\"&inv\"];
+    \"output\" [shape=circle];
+    \"a\" -> \"inv\";
+    \"a\" -> \"con\";
+    \"b\" -> \"con\";
+    \"broadcaster\" -> \"a\";
+    \"con\" -> \"output\";
+    \"inv\" -> \"b\";
+}
+";
+        assert_eq!(Circuit::parse(INTERESTING_EXAMPLE).dot(), expected);
+    }
+
+    #[test]
+    fn mermaid() {
+        let circuit = Circuit::parse(
+            "\
+broadcaster -> a
+%a -> con
+&con -> rx",
+        );
+        let expected = "\
+flowchart LR
+    m_a[\"%a\"]
+    m_broadcaster{{\"broadcaster\"}}
+    m_con{\"&con\"}
+    m_rx(((\"rx\")))
+    style m_rx fill:#f66
+    m_a --> m_con
+    m_broadcaster --> m_a
+    m_con --> m_rx
+";
+        assert_eq!(circuit.mermaid(), expected);
+
+        // `end` is a keyword, and quotes would end a label early.
+        let circuit = Circuit::parse(
+            "\
+broadcaster -> end
+%end -> say \"hi\"",
+        );
+        let expected = "\
+flowchart LR
+    m_broadcaster{{\"broadcaster\"}}
+    m_end[\"%end\"]
+    m_say_20__22_hi_22_((\"say #quot;hi#quot;\"))
+    m_broadcaster --> m_end
+    m_end --> m_say_20__22_hi_22_
+";
+        assert_eq!(circuit.mermaid(), expected);
+    }
+
+    #[test]
+    fn check() {
+        assert_eq!(Circuit::check(EXAMPLE), vec![]);
+        assert_eq!(
+            Circuit::check(INTERESTING_EXAMPLE),
+            vec![Problem::UndefinedDestination {
+                module: "con".to_owned(),
+                destination: "output".to_owned()
+            }]
+        );
+        let problems = Circuit::check(
+            "\
+broadcaster -> a
+%a -> b, rx
+%a -> rx
+&b -> rx
+&lonely -> a",
+        );
+        let problems: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+        // The second definition of a wins, which cuts b off.
+        assert_eq!(
+            problems,
+            [
+                "a is defined more than once",
+                "conjunction b has no inputs",
+                "conjunction lonely has no inputs",
+                "b can't be reached from broadcaster",
+                "lonely can't be reached from broadcaster",
+            ]
+        );
+    }
+
+    #[test]
+    fn compiled() {
+        for input in [EXAMPLE, INTERESTING_EXAMPLE] {
+            let mut circuit = Circuit::parse(input);
+            let mut compiled = CompiledCircuit::compile(&circuit);
+            for _ in 0..10 {
+                assert_eq!(
+                    compiled.push_button(Some("b")),
+                    circuit.push_button(Some("b"))
+                );
+            }
+            // Compiling a circuit mid-run picks up its state.
+            let mut compiled = CompiledCircuit::compile(&circuit);
+            assert_eq!(compiled.push_button(None), circuit.push_button(None));
+        }
+
+        // A conjunction listed twice by the same module has one input for it.
+        let input = "broadcaster -> a\n%a -> con, con\n&con -> output";
+        let mut circuit = Circuit::parse(input);
+        let mut compiled = CompiledCircuit::compile(&circuit);
+        for _ in 0..4 {
+            assert_eq!(compiled.push_button(None), circuit.push_button(None));
+        }
+
+        // Enough pulses at once to make the queue grow.
+        let flip_flops: Vec<String> = (0..100).map(|i| format!("f{i}")).collect();
+        let mut input = format!("broadcaster -> {}", flip_flops.join(", "));
+        for f in &flip_flops {
+            input += &format!("\n%{f} -> output");
+        }
+        let mut circuit = Circuit::parse(&input);
+        let mut compiled = CompiledCircuit::compile(&circuit);
+        for _ in 0..3 {
+            assert_eq!(compiled.push_button(None), circuit.push_button(None));
+        }
+    }
+
+    #[test]
+    fn cycles() {
+        let cycle = Circuit::parse(EXAMPLE).run(1000).2;
+        let expected = Cycle {
+            start: 0,
+            period: 1,
+            low_pulses: 8,
+            high_pulses: 4,
+        };
+        assert_eq!(cycle, Some(expected));
+        let cycle = Circuit::parse(INTERESTING_EXAMPLE).run(1000).2;
+        let expected = Cycle {
+            start: 0,
+            period: 4,
+            low_pulses: 17,
+            high_pulses: 11,
+        };
+        assert_eq!(cycle, Some(expected));
+        assert_eq!(Circuit::parse(INTERESTING_EXAMPLE).run(3).2, None);
+
+        // Jumping ahead leaves the circuit where pushing would have.
+        let mut pushed = Circuit::parse(INTERESTING_EXAMPLE);
+        for _ in 0..6 {
+            pushed.push_button(None);
+        }
+        let mut jumped = Circuit::parse(INTERESTING_EXAMPLE);
+        let (low, high, _) = jumped.run(1_000_000_002);
+        assert_eq!(jumped.state(), pushed.state());
+        assert_eq!(jumped.presses, 1_000_000_002);
+        assert_eq!((low, high), (4_250_000_008, 2_750_000_006));
+    }
+
+    #[test]
+    fn trace() {
+        let mut circuit = Circuit::parse(EXAMPLE);
+        circuit.trace = Some(Trace::default());
+        circuit.push_button(None);
+        let mut out = vec![];
+        circuit.trace.unwrap().write(&mut out).unwrap();
+        let expected = "\
+1 button -low-> broadcaster (0 queued)
+1 broadcaster -low-> a (2 queued)
+1 broadcaster -low-> b (2 queued)
+1 broadcaster -low-> c (2 queued)
+1 a -high-> b (2 queued)
+1 b -high-> c (1 queued)
+1 c -high-> inv (0 queued)
+1 inv -low-> a (0 queued)
+1 a -low-> b (0 queued)
+1 b -low-> c (0 queued)
+1 c -low-> inv (0 queued)
+1 inv -high-> a (0 queued)
+";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+
+        let mut circuit = Circuit::parse(INTERESTING_EXAMPLE);
+        circuit.trace = Some(Trace::only(&["output"]));
+        circuit.simulate(2);
+        let traced: Vec<String> = circuit
+            .trace
+            .unwrap()
+            .pulses
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(
+            traced,
+            [
+                "1 con -high-> output (1 queued)",
+                "1 con -low-> output (0 queued)",
+                "2 con -high-> output (0 queued)",
+            ]
+        );
+    }
+
+    #[test]
+    fn trace_through_cycles() {
+        // This circuit repeats after every press, but each one gets traced.
+        let mut circuit = Circuit::parse(EXAMPLE);
+        circuit.trace = Some(Trace::default());
+        assert_eq!(circuit.simulate(5), (8 * 5) * (4 * 5));
+        let trace = circuit.trace.unwrap();
+        assert_eq!(trace.pulses.len(), 5 * 12);
+        assert_eq!(trace.pulses.last().unwrap().press, 5);
+    }
+
+    #[test]
+    fn part_two() {
+        // Two counters that wrap around after 3 and 5 presses feed the
+        // conjunction in front of rx.
+        let circuit = Circuit::parse(
+            "\
+broadcaster -> a, c
+%a -> b, p
+%b -> p
+&p -> a, x
+&x -> f
+%c -> d, q
+%d -> e
+%e -> q
+&q -> c, d, y
+&y -> f
+&f -> rx",
+        );
+        let mut brute_force = circuit.clone();
+        let expected = (1..)
+            .find(|_| brute_force.push_button(None).low_pulses_to_rx > 0)
+            .unwrap();
+        assert_eq!(expected, 15);
+        assert_eq!(circuit.presses_until_rx(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "gave up after 100000 presses: n never went high twice")]
+    fn part_two_never_fires() {
+        let circuit = Circuit::parse(
+            "\
+broadcaster -> a
+%a -> f
+&n -> f
+&f -> rx",
+        );
+        circuit.presses_until_rx();
+    }
+}
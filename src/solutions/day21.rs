@@ -0,0 +1,110 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::utils::grid::Grid;
+
+struct Garden {
+    grid: Grid<u8>,
+    start: (i64, i64),
+}
+
+impl Garden {
+    fn parse(input: &str) -> Self {
+        let grid = Grid::parse(input);
+        let start = grid.byte_position(b'S').expect("no start");
+        Self { grid, start }
+    }
+
+    /// Is there a garden plot here? The map repeats forever in every direction.
+    fn is_plot(&self, x: i64, y: i64) -> bool {
+        let w = self.grid.width() as i64;
+        let h = self.grid.height() as i64;
+        self.grid.get(x.rem_euclid(w), y.rem_euclid(h)) != b'#'
+    }
+
+    /// How many plots can the elf be on after exactly `steps` steps?
+    fn reachable(&self, steps: usize, infinite: bool) -> usize {
+        let mut distance: HashMap<(i64, i64), usize> = HashMap::from([(self.start, 0)]);
+        let mut queue = VecDeque::from([self.start]);
+        while let Some((x, y)) = queue.pop_front() {
+            let d = distance[&(x, y)];
+            if d == steps {
+                continue;
+            }
+            for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                let (x, y) = (x + dx, y + dy);
+                if (infinite || self.grid.in_range(x, y))
+                    && self.is_plot(x, y)
+                    && !distance.contains_key(&(x, y))
+                {
+                    distance.insert((x, y), d + 1);
+                    queue.push_back((x, y));
+                }
+            }
+        }
+
+        // The elf can waste steps walking back and forth, so every plot reached
+        // in fewer steps of the same parity counts too.
+        distance.values().filter(|&&d| d % 2 == steps % 2).count()
+    }
+
+    /// In the real input, the row and column through S are clear, so the
+    /// reachable area grows by whole copies of the map every `width` steps, and
+    /// the count is quadratic in the number of copies. Sample it three times
+    /// and extrapolate.
+    fn reachable_extrapolated(&self, steps: usize) -> usize {
+        let w = self.grid.width();
+        let (n, r) = (steps / w, steps % w);
+        if n < 3 {
+            return self.reachable(steps, true);
+        }
+        let [a, b, c] = [0, 1, 2].map(|k| self.reachable(r + k * w, true) as i64);
+        let n = n as i64;
+        // Newton's forward difference formula.
+        (a + n * (b - a) + n * (n - 1) / 2 * (c - 2 * b + a)) as usize
+    }
+}
+
+pub fn main(input: &str) {
+    let garden = Garden::parse(input);
+    println!("*  {}", garden.reachable(64, false));
+    println!("** {}", garden.reachable_extrapolated(26501365));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+...........
+.....###.#.
+.###.##..#.
+..#.#...#..
+....#.#....
+.##..S####.
+.##..#...#.
+.......##..
+.##.#.####.
+.##..##.##.
+...........";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(Garden::parse(EXAMPLE).reachable(6, false), 16);
+    }
+
+    #[test]
+    fn part_two() {
+        let garden = Garden::parse(EXAMPLE);
+        for (steps, plots) in [(6, 16), (10, 50), (50, 1594), (100, 6536)] {
+            assert_eq!(garden.reachable(steps, true), plots);
+        }
+    }
+
+    #[test]
+    fn extrapolation() {
+        let garden = Garden::parse(".....\n.....\n..S..\n.....\n.....");
+        let steps = 2 + 5 * 4;
+        assert_eq!(garden.reachable_extrapolated(steps), 529);
+        assert_eq!(garden.reachable(steps, true), 529);
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use crate::utils::parse::integers;
+
+struct Brick {
+    min: [usize; 3],
+    max: [usize; 3],
+}
+
+impl Brick {
+    /// Parse a line like `1,0,1~1,2,1` into a brick.
+    fn parse(line: &str) -> Self {
+        let n: [usize; 6] = integers(line).expect("bad number")[..]
+            .try_into()
+            .expect("bad brick");
+        Self {
+            min: [n[0], n[1], n[2]],
+            max: [n[3], n[4], n[5]],
+        }
+    }
+
+    fn footprint(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.min[0]..=self.max[0]).flat_map(|x| (self.min[1]..=self.max[1]).map(move |y| (x, y)))
+    }
+}
+
+/// The bricks after they've all settled, as a graph of which bricks rest on
+/// which. Bricks are numbered from the ground up, so a brick is always
+/// numbered higher than the bricks holding it up.
+struct Pile {
+    supported_by: Vec<Vec<usize>>,
+    supports: Vec<Vec<usize>>,
+}
+
+impl Pile {
+    fn settle(input: &str) -> Self {
+        let mut bricks: Vec<Brick> = input.split('\n').map(Brick::parse).collect();
+        bricks.sort_by_key(|b| b.min[2]);
+
+        // For each (x, y) column, the height of the top brick and its number.
+        let mut top: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        let mut supported_by = vec![vec![]; bricks.len()];
+        let mut supports = vec![vec![]; bricks.len()];
+        for (i, brick) in bricks.iter().enumerate() {
+            let floor = brick
+                .footprint()
+                .filter_map(|c| top.get(&c))
+                .map(|t| t.0)
+                .max()
+                .unwrap_or(0);
+            let mut below: Vec<usize> = brick
+                .footprint()
+                .filter_map(|c| top.get(&c))
+                .filter(|t| t.0 == floor && floor > 0)
+                .map(|t| t.1)
+                .collect();
+            below.sort();
+            below.dedup();
+            for &j in &below {
+                supports[j].push(i);
+            }
+            supported_by[i] = below;
+
+            let height = floor + 1 + brick.max[2] - brick.min[2];
+            for c in brick.footprint() {
+                top.insert(c, (height, i));
+            }
+        }
+        Self {
+            supported_by,
+            supports,
+        }
+    }
+
+    fn safe_to_disintegrate(&self) -> usize {
+        (0..self.supports.len())
+            .filter(|&i| {
+                self.supports[i]
+                    .iter()
+                    .all(|&j| self.supported_by[j].len() > 1)
+            })
+            .count()
+    }
+
+    /// How many other bricks fall if we disintegrate brick `i`?
+    fn chain_reaction(&self, i: usize) -> usize {
+        let mut fallen = vec![false; self.supports.len()];
+        fallen[i] = true;
+        let mut count = 0;
+        for j in i + 1..self.supports.len() {
+            let below = &self.supported_by[j];
+            if !below.is_empty() && below.iter().all(|&k| fallen[k]) {
+                fallen[j] = true;
+                count += 1;
+            }
+        }
+        count
+    }
+
+    fn chain_reaction_sum(&self) -> usize {
+        (0..self.supports.len())
+            .map(|i| self.chain_reaction(i))
+            .sum()
+    }
+}
+
+pub fn main(input: &str) {
+    let pile = Pile::settle(input);
+    println!("*  {}", pile.safe_to_disintegrate());
+    println!("** {}", pile.chain_reaction_sum());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+1,0,1~1,2,1
+0,0,2~2,0,2
+0,2,3~2,2,3
+0,0,4~0,2,4
+2,0,5~2,2,5
+0,1,6~2,1,6
+1,1,8~1,1,9";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(Pile::settle(EXAMPLE).safe_to_disintegrate(), 5);
+    }
+
+    #[test]
+    fn part_two() {
+        assert_eq!(Pile::settle(EXAMPLE).chain_reaction_sum(), 7);
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use crate::utils::grid::Grid;
+
+const DIRECTIONS: [(i64, i64); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+/// The trail map, boiled down to a graph between junctions (plus the start and
+/// end), with edges weighted by the length of the trail between them.
+struct Trails {
+    edges: Vec<Vec<(usize, usize)>>,
+    start: usize,
+    end: usize,
+}
+
+fn open_neighbors(grid: &Grid<u8>, x: i64, y: i64, slippery: bool) -> Vec<(i64, i64)> {
+    let directions: &[(i64, i64)] = match grid.get(x, y) {
+        b'>' if slippery => &[(1, 0)],
+        b'<' if slippery => &[(-1, 0)],
+        b'v' if slippery => &[(0, 1)],
+        b'^' if slippery => &[(0, -1)],
+        _ => &DIRECTIONS,
+    };
+    directions
+        .iter()
+        .map(|(dx, dy)| (x + dx, y + dy))
+        .filter(|&(x, y)| grid.in_range(x, y) && grid.get(x, y) != b'#')
+        .collect()
+}
+
+impl Trails {
+    fn parse(input: &str, slippery: bool) -> Self {
+        let grid = Grid::<u8>::parse(input);
+        let h = grid.height() as i64;
+        let start = (
+            grid.row(0).iter().position(|&b| b == b'.').unwrap() as i64,
+            0,
+        );
+        let end = (
+            grid.row(h as usize - 1)
+                .iter()
+                .position(|&b| b == b'.')
+                .unwrap() as i64,
+            h - 1,
+        );
+
+        let mut junctions: HashMap<(i64, i64), usize> = HashMap::from([(start, 0), (end, 1)]);
+        for y in 0..h {
+            for x in 0..grid.width() as i64 {
+                if grid.get(x, y) != b'#' && open_neighbors(&grid, x, y, false).len() >= 3 {
+                    let n = junctions.len();
+                    junctions.entry((x, y)).or_insert(n);
+                }
+            }
+        }
+
+        let mut edges = vec![vec![]; junctions.len()];
+        for (&(x, y), &i) in &junctions {
+            for next in open_neighbors(&grid, x, y, slippery) {
+                // Follow the trail until we reach another junction.
+                let (mut previous, mut current) = ((x, y), next);
+                let mut length = 1;
+                let reached = loop {
+                    if let Some(&j) = junctions.get(&current) {
+                        break Some(j);
+                    }
+                    let onward: Vec<(i64, i64)> =
+                        open_neighbors(&grid, current.0, current.1, slippery)
+                            .into_iter()
+                            .filter(|&p| p != previous)
+                            .collect();
+                    match onward[..] {
+                        [p] => (previous, current) = (current, p),
+                        _ => break None,
+                    }
+                    length += 1;
+                };
+                if let Some(j) = reached {
+                    edges[i].push((j, length));
+                }
+            }
+        }
+
+        Self {
+            edges,
+            start: 0,
+            end: 1,
+        }
+    }
+
+    fn longest_hike(&self) -> usize {
+        let mut visited = vec![false; self.edges.len()];
+        self.longest_from(self.start, &mut visited).unwrap()
+    }
+
+    fn longest_from(&self, node: usize, visited: &mut [bool]) -> Option<usize> {
+        if node == self.end {
+            return Some(0);
+        }
+        visited[node] = true;
+        let mut best = None;
+        for &(next, length) in &self.edges[node] {
+            if !visited[next] {
+                if let Some(rest) = self.longest_from(next, visited) {
+                    best = best.max(Some(length + rest));
+                }
+            }
+        }
+        visited[node] = false;
+        best
+    }
+}
+
+pub fn main(input: &str) {
+    println!("*  {}", Trails::parse(input, true).longest_hike());
+    println!("** {}", Trails::parse(input, false).longest_hike());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+#.#####################
+#.......#########...###
+#######.#########.#.###
+###.....#.>.>.###.#.###
+###v#####.#v#.###.#.###
+###.>...#.#.#.....#...#
+###v###.#.#.#########.#
+###...#.#.#.......#...#
+#####.#.#.#######.#.###
+#.....#.#.#.......#...#
+#.#####.#.#.#########v#
+#.#...#...#...###...>.#
+#.#.#v#######v###.###v#
+#...#.>.#...>.>.#.###.#
+#####v#.#.###v#.#.###.#
+#.....#...#...#.#.#...#
+#.#########.###.#.#.###
+#...###...#...#...#.###
+###.###.#.###v#####v###
+#...#...#.#.>.>.#.>.###
+#.###.###.#.###.#.#v###
+#.....###...###...#...#
+#####################.#";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(Trails::parse(EXAMPLE, true).longest_hike(), 94);
+    }
+
+    #[test]
+    fn part_two() {
+        assert_eq!(Trails::parse(EXAMPLE, false).longest_hike(), 154);
+    }
+}
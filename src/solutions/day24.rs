@@ -0,0 +1,173 @@
+use crate::utils::parse::integers;
+
+#[derive(Clone, Copy, Debug)]
+struct Hailstone {
+    position: [i128; 3],
+    velocity: [i128; 3],
+}
+
+impl Hailstone {
+    /// Parse a line like `19, 13, 30 @ -2,  1, -2` into a hailstone.
+    fn parse(line: &str) -> Self {
+        let n: [i128; 6] = integers(line).expect("bad number")[..]
+            .try_into()
+            .expect("bad hailstone");
+        Self {
+            position: [n[0], n[1], n[2]],
+            velocity: [n[3], n[4], n[5]],
+        }
+    }
+
+    /// The times at which this hailstone and `other` pass through the point
+    /// where their paths cross in the xy-plane, if they aren't parallel.
+    fn crossing_times_xy(&self, other: &Hailstone) -> Option<(f64, f64)> {
+        let [ux, uy, _] = self.velocity;
+        let [vx, vy, _] = other.velocity;
+        let det = ux * vy - uy * vx;
+        if det == 0 {
+            return None;
+        }
+        let dx = other.position[0] - self.position[0];
+        let dy = other.position[1] - self.position[1];
+        let t = (dx * vy - dy * vx) as f64 / det as f64;
+        let s = (dx * uy - dy * ux) as f64 / det as f64;
+        Some((t, s))
+    }
+
+    fn at(&self, t: f64) -> (f64, f64) {
+        (
+            self.position[0] as f64 + t * self.velocity[0] as f64,
+            self.position[1] as f64 + t * self.velocity[1] as f64,
+        )
+    }
+
+    /// This hailstone as seen by a rock moving at velocity `v`.
+    fn relative_to(&self, v: [i128; 3]) -> Hailstone {
+        Hailstone {
+            position: self.position,
+            velocity: std::array::from_fn(|i| self.velocity[i] - v[i]),
+        }
+    }
+}
+
+fn future_crossings(hail: &[Hailstone], low: f64, high: f64) -> usize {
+    let mut count = 0;
+    for (i, a) in hail.iter().enumerate() {
+        for b in &hail[i + 1..] {
+            if let Some((t, s)) = a.crossing_times_xy(b) {
+                let (x, y) = a.at(t);
+                if t >= 0.0 && s >= 0.0 && (low..=high).contains(&x) && (low..=high).contains(&y) {
+                    count += 1;
+                }
+            }
+        }
+    }
+    count
+}
+
+/// If the rock moves at `(vx, vy)` in the xy-plane, then in its frame of
+/// reference every hailstone has to pass through the single point the rock
+/// starts at. Find that point, or `None` if there isn't one.
+fn rock_start(hail: &[Hailstone], vx: i128, vy: i128) -> Option<[i128; 3]> {
+    let v = [vx, vy, 0];
+    let relative = || hail[1..].iter().map(|h| h.relative_to(v));
+    let a = hail[0].relative_to(v);
+    let [ux, uy, _] = a.velocity;
+
+    // Intersect the first hailstone's path with the next one it isn't parallel
+    // to, in the xy-plane, to find when the first one gets hit.
+    let t = relative().find_map(|b| {
+        let [vx, vy, _] = b.velocity;
+        let det = ux * vy - uy * vx;
+        let dx = b.position[0] - a.position[0];
+        let dy = b.position[1] - a.position[1];
+        (det != 0).then(|| (dx * vy - dy * vx, det))
+    });
+    let (num, det) = t?;
+    if num % det != 0 {
+        return None;
+    }
+    let t0 = num / det;
+    let [x, y] = [a.position[0] + t0 * ux, a.position[1] + t0 * uy];
+
+    // Now work out when every other hailstone gets hit, from x and y alone.
+    let mut times = vec![t0];
+    for h in relative() {
+        let [hx, hy, _] = h.velocity;
+        let t = if hx != 0 {
+            (x - h.position[0]) / hx
+        } else if hy != 0 {
+            (y - h.position[1]) / hy
+        } else {
+            return None;
+        };
+        if t < 0 || h.position[0] + t * hx != x || h.position[1] + t * hy != y {
+            return None;
+        }
+        times.push(t);
+    }
+
+    // Finally, the z velocity is whatever makes the first two collisions with
+    // different times happen at the same height.
+    let (i, &t1) = times.iter().enumerate().find(|(_, &t)| t != t0)?;
+    let (p, q) = (&hail[0], &hail[i]);
+    let num = (p.position[2] + t0 * p.velocity[2]) - (q.position[2] + t1 * q.velocity[2]);
+    if num % (t0 - t1) != 0 {
+        return None;
+    }
+    let vz = num / (t0 - t1);
+    let z = p.position[2] + t0 * (p.velocity[2] - vz);
+    hail.iter()
+        .zip(&times)
+        .all(|(h, &t)| h.position[2] + t * (h.velocity[2] - vz) == z)
+        .then_some([x, y, z])
+}
+
+/// Search velocities in the xy-plane outward from zero until one works.
+fn throw_rock(hail: &[Hailstone], limit: i128) -> Option<[i128; 3]> {
+    for r in 0..=limit {
+        for vx in -r..=r {
+            for vy in -r..=r {
+                if vx.abs() == r || vy.abs() == r {
+                    if let Some(p) = rock_start(hail, vx, vy) {
+                        return Some(p);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+pub fn main(input: &str) {
+    let hail: Vec<Hailstone> = input.split('\n').map(Hailstone::parse).collect();
+    println!("*  {}", future_crossings(&hail, 2e14, 4e14));
+    let rock = throw_rock(&hail, 1000).expect("no rock throw found");
+    println!("** {}", rock.iter().sum::<i128>());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+19, 13, 30 @ -2,  1, -2
+18, 19, 22 @ -1, -1, -2
+20, 25, 34 @ -2, -2, -4
+12, 31, 28 @ -1, -2, -1
+20, 19, 15 @  1, -5, -3";
+
+    fn example() -> Vec<Hailstone> {
+        EXAMPLE.split('\n').map(Hailstone::parse).collect()
+    }
+
+    #[test]
+    fn part_one() {
+        assert_eq!(future_crossings(&example(), 7.0, 27.0), 2);
+    }
+
+    #[test]
+    fn part_two() {
+        assert_eq!(throw_rock(&example(), 10), Some([24, 13, 10]));
+    }
+}
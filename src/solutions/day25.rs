@@ -0,0 +1,107 @@
+use std::collections::{HashMap, VecDeque};
+
+/// The components and the wires between them, with component names replaced
+/// by indices.
+struct Wiring {
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl Wiring {
+    fn parse(input: &str) -> Self {
+        let mut names: HashMap<&str, usize> = HashMap::new();
+        let mut neighbors: Vec<Vec<usize>> = vec![];
+        let mut index = |name| {
+            let n = names.len();
+            *names.entry(name).or_insert(n)
+        };
+        for line in input.split('\n') {
+            let (src, dsts) = line.split_once(": ").expect("no colon");
+            let src = index(src);
+            for dst in dsts.split(' ') {
+                let dst = index(dst);
+                neighbors.resize(neighbors.len().max(src.max(dst) + 1), vec![]);
+                neighbors[src].push(dst);
+                neighbors[dst].push(src);
+            }
+        }
+        Self { neighbors }
+    }
+
+    /// Push flow from `source` to `sink` along wires of capacity 1, stopping
+    /// once it exceeds `limit`. Returns the flow and which components are still
+    /// reachable from `source` in the residual graph.
+    fn max_flow(&self, source: usize, sink: usize, limit: usize) -> (usize, Vec<bool>) {
+        let mut flow: HashMap<(usize, usize), i32> = HashMap::new();
+        let mut total = 0;
+        loop {
+            // Find an augmenting path by breadth-first search.
+            let mut parent = vec![None; self.neighbors.len()];
+            let mut reached = vec![false; self.neighbors.len()];
+            reached[source] = true;
+            let mut queue = VecDeque::from([source]);
+            while let Some(u) = queue.pop_front() {
+                for &v in &self.neighbors[u] {
+                    let used = flow.get(&(u, v)).copied().unwrap_or(0);
+                    if !reached[v] && used < 1 {
+                        reached[v] = true;
+                        parent[v] = Some(u);
+                        queue.push_back(v);
+                    }
+                }
+            }
+            if !reached[sink] || total > limit {
+                return (total, reached);
+            }
+            let mut v = sink;
+            while let Some(u) = parent[v] {
+                *flow.entry((u, v)).or_default() += 1;
+                *flow.entry((v, u)).or_default() -= 1;
+                v = u;
+            }
+            total += 1;
+        }
+    }
+
+    /// Find the three wires that split the components into two groups, and
+    /// multiply the sizes of the groups.
+    fn split_product(&self) -> usize {
+        let n = self.neighbors.len();
+        for sink in 1..n {
+            let (flow, side) = self.max_flow(0, sink, 3);
+            if flow == 3 {
+                let size = side.iter().filter(|&&b| b).count();
+                return size * (n - size);
+            }
+        }
+        panic!("no three-wire cut found");
+    }
+}
+
+pub fn main(input: &str) {
+    println!("*  {}", Wiring::parse(input).split_product());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+jqt: rhn xhk nvd
+rsh: frs pzl lsr
+xhk: hfx
+cmg: qnr nvd lhk bvb
+rhn: xhk bvb hfx
+bvb: xhk hfx
+pzl: lsr hfx nvd
+qnr: nvd
+ntq: jqt hfx bvb xhk
+nvd: lhk
+lsr: lhk
+rzs: qnr cmg lsr rsh
+frs: qnr lhk lsr";
+
+    #[test]
+    fn part_one() {
+        assert_eq!(Wiring::parse(EXAMPLE).split_product(), 54);
+    }
+}
@@ -0,0 +1,77 @@
+//! Extra output for looking at a puzzle more closely, like graphs and traces.
+//! It goes into files in the directory named by the `AOC_EXPORT` environment
+//! variable, and isn't even computed if that isn't set.
+
+use std::{env, fs, path::PathBuf};
+
+/// Where to write the export called `name`, if we're exporting.
+pub fn path(name: &str) -> Option<PathBuf> {
+    let dir = PathBuf::from(env::var_os("AOC_EXPORT")?);
+    fs::create_dir_all(&dir).expect("couldn't create export directory");
+    Some(dir.join(name))
+}
+
+/// Write the export called `name`, if we're exporting.
+pub fn write(name: &str, contents: impl FnOnce() -> String) {
+    if let Some(path) = path(name) {
+        fs::write(&path, contents()).expect("couldn't write export");
+        eprintln!("wrote {}", path.display());
+    }
+}
+
+/// Quote a name as a Graphviz ID, escaping anything that would end it early.
+pub fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Turn a name into a Mermaid node ID. The prefix keeps names like `end` from
+/// being read as keywords, and anything but letters and digits is spelled out
+/// in hex, so different names always get different IDs.
+pub fn mermaid_id(name: &str) -> String {
+    let mut id = String::from("m_");
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c);
+        } else {
+            id += &format!("_{:x}_", c as u32);
+        }
+    }
+    id
+}
+
+/// Quote a name as a Mermaid label, using entity codes for anything that
+/// would end the label or be read as markup.
+pub fn mermaid_label(name: &str) -> String {
+    let mut label = String::from("\"");
+    for c in name.chars() {
+        match c {
+            '"' => label += "#quot;",
+            '#' => label += "#35;",
+            '<' => label += "#lt;",
+            '>' => label += "#gt;",
+            _ => label.push(c),
+        }
+    }
+    label.push('"');
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dot_ids() {
+        assert_eq!(dot_id("abc"), r#""abc""#);
+        assert_eq!(dot_id(r#"a"b\c"#), r#""a\"b\\c""#);
+    }
+
+    #[test]
+    fn mermaid_ids() {
+        assert_eq!(mermaid_id("end"), "m_end");
+        assert_eq!(mermaid_id("a_b c"), "m_a_5f_b_20_c");
+        assert_ne!(mermaid_id("a_"), mermaid_id("a_5f_"));
+        assert_eq!(mermaid_label("&con"), r#""&con""#);
+        assert_eq!(mermaid_label(r#"a"b#1<2>"#), r#""a#quot;b#35;1#lt;2#gt;""#);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::utils::math::gcd;
+
+/// A closed polygon on the integer lattice, given by its vertices in order.
+/// The last vertex connects back to the first.
+pub struct Polygon {
+    vertices: Vec<(i64, i64)>,
+}
+
+impl Polygon {
+    pub fn new(vertices: Vec<(i64, i64)>) -> Self {
+        Self { vertices }
+    }
+
+    fn edges(&self) -> impl Iterator<Item = ((i64, i64), (i64, i64))> + '_ {
+        let next = self.vertices.iter().cycle().skip(1);
+        self.vertices.iter().copied().zip(next.copied())
+    }
+
+    /// Twice the signed area, by the shoelace formula. This is always an
+    /// integer. It's positive when the vertices go counter-clockwise in a y-up
+    /// frame (which is clockwise on a screen, where y points down).
+    pub fn twice_signed_area(&self) -> i64 {
+        self.edges()
+            .map(|((x1, y1), (x2, y2))| x1 * y2 - x2 * y1)
+            .sum()
+    }
+
+    /// The area enclosed by the polygon, rounded down to an integer.
+    #[allow(dead_code)]
+    pub fn area(&self) -> i64 {
+        self.twice_signed_area().abs() / 2
+    }
+
+    /// The Euclidean length of the boundary.
+    #[allow(dead_code)]
+    pub fn perimeter(&self) -> f64 {
+        self.edges()
+            .map(|((x1, y1), (x2, y2))| ((x2 - x1) as f64).hypot((y2 - y1) as f64))
+            .sum()
+    }
+
+    /// How many lattice points lie on the boundary.
+    pub fn boundary_points(&self) -> i64 {
+        self.edges()
+            .map(|((x1, y1), (x2, y2))| gcd(x2 - x1, y2 - y1))
+            .sum()
+    }
+
+    /// How many lattice points lie strictly inside the polygon, by Pick's
+    /// theorem: A = i + b/2 - 1.
+    pub fn interior_points(&self) -> i64 {
+        (self.twice_signed_area().abs() - self.boundary_points() + 2) / 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rectangle() {
+        let p = Polygon::new(vec![(0, 0), (4, 0), (4, 3), (0, 3)]);
+        assert_eq!(p.twice_signed_area(), 24);
+        assert_eq!(p.area(), 12);
+        assert_eq!(p.perimeter(), 14.0);
+        assert_eq!(p.boundary_points(), 14);
+        assert_eq!(p.interior_points(), 6);
+    }
+
+    #[test]
+    fn orientation() {
+        let p = Polygon::new(vec![(0, 0), (0, 3), (4, 3), (4, 0)]);
+        assert_eq!(p.twice_signed_area(), -24);
+        assert_eq!(p.area(), 12);
+        assert_eq!(p.interior_points(), 6);
+    }
+
+    #[test]
+    fn diagonals() {
+        // A 3-4-5 triangle, in negative coordinates.
+        let p = Polygon::new(vec![(-3, -4), (0, -4), (0, 0)]);
+        assert_eq!(p.area(), 6);
+        assert_eq!(p.perimeter(), 12.0);
+        assert_eq!(p.boundary_points(), 8);
+        assert_eq!(p.interior_points(), 3);
+        // Half-integer areas get rounded down.
+        let p = Polygon::new(vec![(0, 0), (3, 0), (0, 1)]);
+        assert_eq!(p.twice_signed_area(), 3);
+        assert_eq!(p.area(), 1);
+    }
+}
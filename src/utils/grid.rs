@@ -18,11 +18,10 @@ impl<T> Grid<T> {
             bytes,
             width,
             height,
-            marker: PhantomData::default(),
+            marker: PhantomData,
         }
     }
 
-    #[allow(dead_code)]
     pub fn string(&self) -> &str {
         std::str::from_utf8(&self.bytes).unwrap()
     }
@@ -0,0 +1,246 @@
+use std::ops::{Add, Range};
+
+/// A set of integers, stored as a sorted list of disjoint half-open ranges.
+/// Touching ranges are merged, so every set has exactly one representation.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct IntervalSet {
+    ranges: Vec<Range<i64>>,
+}
+
+impl IntervalSet {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Sort and merge arbitrary ranges into a well-formed set.
+    fn normalize(mut ranges: Vec<Range<i64>>) -> Self {
+        ranges.retain(|r| r.start < r.end);
+        ranges.sort_by_key(|r| r.start);
+        let mut merged: Vec<Range<i64>> = Vec::with_capacity(ranges.len());
+        for r in ranges {
+            match merged.last_mut() {
+                Some(last) if r.start <= last.end => last.end = last.end.max(r.end),
+                _ => merged.push(r),
+            }
+        }
+        Self { ranges: merged }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The disjoint ranges making up this set, in increasing order.
+    pub fn ranges(&self) -> impl Iterator<Item = Range<i64>> + '_ {
+        self.ranges.iter().cloned()
+    }
+
+    /// The smallest element of the set.
+    pub fn lower(&self) -> Option<i64> {
+        self.ranges.first().map(|r| r.start)
+    }
+
+    /// The largest element of the set.
+    #[allow(dead_code)]
+    pub fn upper(&self) -> Option<i64> {
+        self.ranges.last().map(|r| r.end - 1)
+    }
+
+    /// How many integers are in the set.
+    pub fn total_length(&self) -> i64 {
+        self.ranges.iter().map(|r| r.end - r.start).sum()
+    }
+
+    #[allow(dead_code)]
+    pub fn contains(&self, x: i64) -> bool {
+        let i = self.ranges.partition_point(|r| r.end <= x);
+        i < self.ranges.len() && self.ranges[i].start <= x
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::normalize(self.ranges().chain(other.ranges()).collect())
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let a = &self.ranges[i];
+            let b = &other.ranges[j];
+            let start = a.start.max(b.start);
+            let end = a.end.min(b.end);
+            if start < end {
+                ranges.push(start..end);
+            }
+            // Advance whichever range ends first; it can't overlap anything else.
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = vec![];
+        let mut j = 0;
+        for r in &self.ranges {
+            let mut start = r.start;
+            while j < other.ranges.len() && other.ranges[j].end <= start {
+                j += 1;
+            }
+            let mut k = j;
+            while k < other.ranges.len() && other.ranges[k].start < r.end {
+                let hole = &other.ranges[k];
+                if start < hole.start {
+                    ranges.push(start..hole.start);
+                }
+                start = start.max(hole.end);
+                k += 1;
+            }
+            if start < r.end {
+                ranges.push(start..r.end);
+            }
+        }
+        Self { ranges }
+    }
+
+    /// Split the set into the elements below `point` and those at or above it.
+    pub fn split_at(&self, point: i64) -> (Self, Self) {
+        let mut below = vec![];
+        let mut above = vec![];
+        for r in self.ranges() {
+            if r.end <= point {
+                below.push(r);
+            } else if r.start >= point {
+                above.push(r);
+            } else {
+                below.push(r.start..point);
+                above.push(point..r.end);
+            }
+        }
+        (Self { ranges: below }, Self { ranges: above })
+    }
+}
+
+impl From<Range<i64>> for IntervalSet {
+    fn from(range: Range<i64>) -> Self {
+        Self::normalize(vec![range])
+    }
+}
+
+impl FromIterator<Range<i64>> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Range<i64>>>(iter: I) -> Self {
+        Self::normalize(iter.into_iter().collect())
+    }
+}
+
+/// Shift every element of the set by an offset.
+impl Add<i64> for IntervalSet {
+    type Output = Self;
+
+    fn add(self, offset: i64) -> Self {
+        Self {
+            ranges: self
+                .ranges
+                .into_iter()
+                .map(|r| r.start + offset..r.end + offset)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(ranges: &[Range<i64>]) -> IntervalSet {
+        ranges.iter().cloned().collect()
+    }
+
+    #[test]
+    fn normalizing() {
+        // Touching and overlapping ranges merge; empty ones disappear.
+        assert_eq!(set(&[3..5, 0..3, 8..9, 7..8, 4..4]), set(&[0..5, 7..9]));
+        let (start, end) = (5, 2);
+        assert!(IntervalSet::from(start..end).is_empty());
+        let s = set(&[-10..-5, 0..3]);
+        assert_eq!(s.lower(), Some(-10));
+        assert_eq!(s.upper(), Some(2));
+        assert_eq!(IntervalSet::empty().upper(), None);
+        assert_eq!(s.total_length(), 8);
+    }
+
+    #[test]
+    fn membership() {
+        let s = set(&[-10..-5, 0..3]);
+        assert!(s.contains(-10));
+        assert!(!s.contains(-5));
+        assert!(s.contains(2));
+        assert!(!s.contains(3));
+        assert!(!s.contains(-1));
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        assert_eq!(
+            IntervalSet::from(0..3).union(&IntervalSet::from(3..6)),
+            IntervalSet::from(0..6)
+        );
+        assert_eq!(
+            IntervalSet::from(0..3).union(&IntervalSet::from(4..6)),
+            set(&[0..3, 4..6])
+        );
+        // Touching ranges don't share any elements.
+        assert_eq!(
+            IntervalSet::from(0..3).intersection(&IntervalSet::from(3..6)),
+            IntervalSet::empty()
+        );
+        assert_eq!(
+            set(&[0..10, 20..30]).intersection(&IntervalSet::from(5..25)),
+            set(&[5..10, 20..25])
+        );
+    }
+
+    #[test]
+    fn difference() {
+        let s = IntervalSet::from(0..10);
+        assert_eq!(
+            s.difference(&IntervalSet::from(0..10)),
+            IntervalSet::empty()
+        );
+        assert_eq!(s.difference(&IntervalSet::from(10..20)), s);
+        assert_eq!(s.difference(&IntervalSet::from(-5..0)), s);
+        assert_eq!(s.difference(&set(&[0..1, 9..10])), IntervalSet::from(1..9));
+        assert_eq!(s.difference(&set(&[2..3, 5..7])), set(&[0..2, 3..5, 7..10]));
+        assert_eq!(
+            set(&[0..5, 10..15]).difference(&IntervalSet::from(3..12)),
+            set(&[0..3, 12..15])
+        );
+    }
+
+    #[test]
+    fn split_at() {
+        let s = set(&[0..5, 10..15]);
+        assert_eq!(s.split_at(0), (IntervalSet::empty(), s.clone()));
+        assert_eq!(s.split_at(15), (s.clone(), IntervalSet::empty()));
+        assert_eq!(
+            s.split_at(5),
+            (IntervalSet::from(0..5), IntervalSet::from(10..15))
+        );
+        assert_eq!(
+            s.split_at(10),
+            (IntervalSet::from(0..5), IntervalSet::from(10..15))
+        );
+        assert_eq!(
+            s.split_at(3),
+            (IntervalSet::from(0..3), set(&[3..5, 10..15]))
+        );
+    }
+
+    #[test]
+    fn shifting() {
+        assert_eq!(set(&[0..5, 10..15]) + -10, set(&[-10..-5, 0..5]));
+    }
+}
@@ -0,0 +1,215 @@
+use std::ops::{Div, Mul, Rem};
+
+/// The primitive integer types we do number theory on.
+pub trait Integer:
+    Copy + Ord + Mul<Output = Self> + Div<Output = Self> + Rem<Output = Self>
+{
+    const ZERO: Self;
+
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn checked_neg(self) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Integer for $t {
+                const ZERO: Self = 0;
+
+                fn checked_mul(self, other: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, other)
+                }
+
+                fn checked_neg(self) -> Option<Self> {
+                    <$t>::checked_neg(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// `|a|`, or `None` if it doesn't fit (like `|i64::MIN|`).
+fn checked_abs<T: Integer>(a: T) -> Option<T> {
+    if a < T::ZERO {
+        a.checked_neg()
+    } else {
+        Some(a)
+    }
+}
+
+/// `gcd(a, b)`, or `None` if it doesn't fit. That only happens when it's
+/// `|T::MIN|`, like for `gcd(i64::MIN, 0)`.
+fn checked_gcd<T: Integer>(mut a: T, mut b: T) -> Option<T> {
+    while b != T::ZERO {
+        // a % b == a % -b, but `MIN % -1` overflows, so divide by |b| if we can.
+        let r = match b.checked_neg() {
+            Some(n) if b < T::ZERO => a % n,
+            _ => a % b,
+        };
+        (a, b) = (b, r)
+    }
+    checked_abs(a)
+}
+
+/// The greatest common divisor of `a` and `b`, which is never negative.
+///
+/// Panics if that doesn't fit in `T`, like for `gcd(i64::MIN, 0)`.
+pub fn gcd<T: Integer>(a: T, b: T) -> T {
+    checked_gcd(a, b).expect("gcd overflows")
+}
+
+/// The least common multiple of `a` and `b`, or `None` if it overflows.
+#[allow(dead_code)]
+pub fn lcm<T: Integer>(a: T, b: T) -> Option<T> {
+    if a == T::ZERO || b == T::ZERO {
+        return Some(T::ZERO);
+    }
+    // Divide first, so that we only overflow if the result really doesn't fit.
+    checked_abs(a / checked_gcd(a, b)?)?.checked_mul(checked_abs(b)?)
+}
+
+/// Find `(g, x, y)` such that `a*x + b*y == g == gcd(a, b)`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut r0, mut r1) = (a, b);
+    let (mut x0, mut x1) = (1, 0);
+    let (mut y0, mut y1) = (0, 1);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+        (y0, y1) = (y1, y0 - q * y1);
+    }
+    if r0 < 0 {
+        (-r0, -x0, -y0)
+    } else {
+        (r0, x0, y0)
+    }
+}
+
+/// The `x` in `0..m` with `a*x ≡ 1 (mod m)`, if `a` and `m` are coprime.
+#[allow(dead_code)]
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+    (g == 1).then(|| x.rem_euclid(m))
+}
+
+/// Solve a system of congruences `x ≡ r (mod m)`, given as `(r, m)` pairs.
+///
+/// The moduli don't need to be coprime. Returns `(x, lcm)` where `x` is the
+/// smallest non-negative solution and every solution is `x` plus a multiple of
+/// `lcm`, or `None` if the congruences contradict each other, a modulus isn't
+/// positive, or the combined modulus overflows.
+pub fn crt(congruences: &[(i128, i128)]) -> Option<(i128, i128)> {
+    let mut x: i128 = 0;
+    let mut m: i128 = 1;
+    for &(r, n) in congruences {
+        if n <= 0 {
+            return None;
+        }
+        // We want x + m*k ≡ r (mod n), so m*k ≡ r - x (mod n).
+        let (g, p, _) = extended_gcd(m, n);
+        let diff = r.rem_euclid(n) - x;
+        if diff % g != 0 {
+            return None;
+        }
+        let step = n / g;
+        let k = (diff / g % step).checked_mul(p % step)?.rem_euclid(step);
+        x = x.checked_add(m.checked_mul(k)?)?;
+        m = m.checked_mul(step)?;
+        x = x.rem_euclid(m);
+    }
+    Some((x, m))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gcd_and_lcm() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(-12, 18), 6);
+        assert_eq!(gcd(12, -18), 6);
+        assert_eq!(gcd(0, -5), 5);
+        assert_eq!(gcd(0, 0), 0);
+        assert_eq!(lcm(4, 6), Some(12));
+        assert_eq!(lcm(-4, 6), Some(12));
+        assert_eq!(lcm(0, 6), Some(0));
+        assert_eq!(lcm(1u8 << 7, 3), None);
+        assert_eq!(lcm(u64::MAX, u64::MAX), Some(u64::MAX));
+        // |i64::MIN| doesn't fit in an i64.
+        assert_eq!(gcd(i64::MIN, 2), 2);
+        assert_eq!(gcd(i64::MIN, -1), 1);
+        assert_eq!(gcd(-1, i64::MIN), 1);
+        assert_eq!(lcm(i64::MIN, 1), None);
+        assert_eq!(lcm(1, i64::MIN), None);
+        assert_eq!(lcm(i64::MIN, i64::MIN), None);
+        assert_eq!(lcm(i64::MIN / 2, 2), Some(1 << 62));
+    }
+
+    #[test]
+    fn every_i8() {
+        // Check against i32, where nothing overflows.
+        for a in i8::MIN..=i8::MAX {
+            for b in i8::MIN..=i8::MAX {
+                let (wide_a, wide_b) = (a as i32, b as i32);
+                if let Ok(g) = i8::try_from(gcd(wide_a, wide_b)) {
+                    assert_eq!(gcd(a, b), g, "gcd({a}, {b})");
+                }
+                let l = lcm(wide_a, wide_b).unwrap();
+                assert_eq!(lcm(a, b), i8::try_from(l).ok(), "lcm({a}, {b})");
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "gcd overflows")]
+    fn gcd_overflow() {
+        gcd(i64::MIN, 0);
+    }
+
+    #[test]
+    fn extended_gcd_signs() {
+        for (a, b) in [
+            (240, 46),
+            (-240, 46),
+            (240, -46),
+            (-240, -46),
+            (0, -7),
+            (7, 0),
+        ] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(g, gcd(a, b), "gcd of {a} and {b}");
+            assert_eq!(a * x + b * y, g, "coefficients for {a} and {b}");
+        }
+    }
+
+    #[test]
+    fn inverses() {
+        assert_eq!(mod_inverse(3, 7), Some(5));
+        assert_eq!(mod_inverse(-3, 7), Some(2));
+        assert_eq!(mod_inverse(10, 7), Some(5));
+        assert_eq!(mod_inverse(2, 4), None);
+    }
+
+    #[test]
+    fn chinese_remainders() {
+        assert_eq!(crt(&[]), Some((0, 1)));
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        // Negative and out-of-range remainders are reduced.
+        assert_eq!(crt(&[(-1, 3), (8, 5)]), Some((8, 15)));
+        assert_eq!(crt(&[(1, 2), (i128::MIN, 3)]), Some((1, 6)));
+        // Moduli that share a factor only need to agree on it.
+        assert_eq!(crt(&[(2, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(crt(&[(3, 6), (3, 6)]), Some((3, 6)));
+        // ...and if they don't, there's no solution.
+        assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+        // Moduli have to be positive.
+        assert_eq!(crt(&[(1, 0)]), None);
+        assert_eq!(crt(&[(2, 3), (1, -5)]), None);
+        // The combined modulus would be 2^200.
+        assert_eq!(crt(&[(1, (1 << 100) - 1), (0, 1 << 100)]), None);
+    }
+}
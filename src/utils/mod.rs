@@ -1 +1,6 @@
+pub mod export;
+pub mod geometry;
 pub mod grid;
+pub mod interval;
+pub mod math;
+pub mod parse;
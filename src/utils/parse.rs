@@ -0,0 +1,123 @@
+use std::{fmt, str::FromStr};
+
+/// Something in the input didn't have the shape we expected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub text: String,
+    pub expected: &'static str,
+}
+
+impl ParseError {
+    fn new(text: &str, expected: &'static str) -> Self {
+        Self {
+            text: text.to_owned(),
+            expected,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}, got {:?}", self.expected, self.text)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Pull every integer out of a line, ignoring whatever is around them. A `-`
+/// right before a digit counts as a minus sign, so `x=-3, y=12` gives `[-3, 12]`.
+/// Numbers that don't fit in `T` (including negative ones, if it's unsigned)
+/// are an error.
+pub fn integers<T: FromStr>(line: &str) -> Result<Vec<T>, ParseError> {
+    let bytes = line.as_bytes();
+    let mut result = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let negative = bytes[i] == b'-' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit);
+        if !negative && !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        let text = &line[start..i];
+        result.push(
+            text.parse()
+                .map_err(|_| ParseError::new(text, "an integer"))?,
+        );
+    }
+    Ok(result)
+}
+
+/// Split the input into blank-line-separated paragraphs.
+pub fn paragraphs(input: &str) -> impl Iterator<Item = &str> {
+    input.split("\n\n")
+}
+
+/// Split `text` by `delimiter` and parse every non-empty token, so that runs of
+/// spaces (like `41 48  3`) don't produce empty tokens.
+pub fn tokens<T: FromStr>(text: &str, delimiter: &str) -> Result<Vec<T>, ParseError> {
+    text.split(delimiter)
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|t| t.parse().map_err(|_| ParseError::new(t, "a value")))
+        .collect()
+}
+
+/// Parse a line like `Time:      7  15   30` into its key and whitespace
+/// separated values.
+pub fn key_values<T: FromStr>(line: &str) -> Result<(&str, Vec<T>), ParseError> {
+    let (key, values) = line
+        .split_once(':')
+        .ok_or_else(|| ParseError::new(line, "a `key: values` line"))?;
+    Ok((key.trim(), tokens(values, " ")?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers_in_a_line() {
+        assert_eq!(integers::<i64>("x=-3, y=12"), Ok(vec![-3, 12]));
+        assert_eq!(integers::<i64>("a-b 4-5"), Ok(vec![4, -5]));
+        assert_eq!(integers::<i64>("no numbers"), Ok(vec![]));
+    }
+
+    #[test]
+    fn integers_out_of_range() {
+        assert_eq!(
+            integers::<u8>("300 5"),
+            Err(ParseError::new("300", "an integer"))
+        );
+        assert_eq!(
+            integers::<usize>("-3"),
+            Err(ParseError::new("-3", "an integer"))
+        );
+    }
+
+    #[test]
+    fn tokens_and_key_values() {
+        assert_eq!(tokens::<u32>("41 48  3 ", " "), Ok(vec![41, 48, 3]));
+        assert_eq!(
+            tokens::<u32>("1,x", ","),
+            Err(ParseError::new("x", "a value"))
+        );
+        assert_eq!(
+            key_values::<u32>("Time:      7  15   30"),
+            Ok(("Time", vec![7, 15, 30]))
+        );
+        assert!(key_values::<u32>("Time 7").is_err());
+        let error = key_values::<u32>("Distance: 9 -1").unwrap_err();
+        assert_eq!(error.to_string(), r#"expected a value, got "-1""#);
+    }
+
+    #[test]
+    fn paragraphs_split_on_blank_lines() {
+        let split: Vec<&str> = paragraphs("a\nb\n\nc").collect();
+        assert_eq!(split, ["a\nb", "c"]);
+    }
+}